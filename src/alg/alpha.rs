@@ -0,0 +1,81 @@
+use image::{Rgba, RgbaImage};
+
+use Image;
+
+use super::dihedral::px_to_rgba;
+
+/// Composite the image over an opaque background color, or return `None` if the image is
+/// already opaque.
+///
+/// The color channels are weighted by their alpha (premultiplied) before adding the
+/// background weighted by the remaining coverage, so fully transparent pixels become the
+/// background color no matter what color is stored in them.
+pub fn composite_over<I: Image>(img: &I, background: [u8; 3]) -> Option<RgbaImage> {
+    let mut opaque = true;
+
+    img.foreach_pixel8(|_, _, px| {
+        if let 2 | 4 = px.len() {
+            opaque &= px[px.len() - 1] == 255;
+        }
+    });
+
+    if opaque {
+        return None;
+    }
+
+    let (width, height) = img.dimensions();
+    let mut composited = RgbaImage::new(width, height);
+
+    img.foreach_pixel8(|x, y, px| {
+        let [r, g, b, alpha] = px_to_rgba(px);
+
+        let blend = |c: u8, bg: u8| {
+            let (c, bg, alpha) = (c as u32, bg as u32, alpha as u32);
+            ((c * alpha + bg * (255 - alpha) + 127) / 255) as u8
+        };
+
+        composited.put_pixel(x, y, Rgba([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+            255,
+        ]));
+    });
+
+    Some(composited)
+}
+
+#[test]
+fn test_composite_over() {
+    use image::{Rgb, RgbImage};
+    use {HashAlg, HasherConfig};
+
+    let half_red = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 128]));
+    let composited = composite_over(&half_red, [255, 255, 255]).unwrap();
+    assert_eq!(composited.get_pixel(0, 0), &Rgba([255, 127, 127, 255]));
+
+    assert!(composite_over(&RgbImage::new(4, 4), [0, 0, 0]).is_none());
+
+    // the same shape, with different colors stored in the transparent pixels
+    let shape = |hidden: u8| RgbaImage::from_fn(32, 32, |x, y| {
+        if (x as i32 - 16).pow(2) + (y as i32 - 16).pow(2) < 100 {
+            Rgba([(x * 8) as u8, 40, (y * 8) as u8, 255])
+        } else {
+            Rgba([hidden, hidden, hidden, 0])
+        }
+    });
+
+    let flattened = RgbImage::from_fn(32, 32, |x, y| {
+        let Rgba([r, g, b, _]) = *composite_over(&shape(0), [255, 255, 255]).unwrap().get_pixel(x, y);
+        Rgb([r, g, b])
+    });
+
+    for &alg in &[HashAlg::Mean, HashAlg::Gradient, HashAlg::Blockhash, HashAlg::Pdq] {
+        let hasher = HasherConfig::new().hash_alg(alg).hash_size(16, 16)
+            .composite_alpha([255, 255, 255]).to_hasher();
+
+        let hash = hasher.hash_image(&shape(0));
+        assert_eq!(hash, hasher.hash_image(&shape(200)), "{:?}", alg);
+        assert_eq!(hash, hasher.hash_image(&flattened), "{:?}", alg);
+    }
+}
@@ -1,16 +1,15 @@
 // Implementation adapted from Python version:
 // https://github.com/commonsmachinery/blockhash-python/blob/e8b009d/blockhash.py
 // Main site: http://blockhash.io
-use image::{GenericImageView, Pixel};
-
-use {BitSet, Image, HashBytes};
+use {Image, HashBytes};
 
 use std::cmp::Ordering;
-use std::ops::AddAssign;
 use std::mem;
 
-const FLOAT_EQ_MARGIN: f32 = 0.001;
-
+/// The "precise" method, which weights pixels straddling block boundaries between the blocks.
+///
+/// Uses the quick method when the image dimensions are multiples of the hash dimensions as the
+/// results are the same.
 pub fn blockhash<I: Image, B: HashBytes>(img: &I, width: u32, height: u32) -> B {
     assert_eq!(width % 4, 0, "width must be multiple of 4");
     assert_eq!(height % 4, 0, "height must be multiple of 4");
@@ -19,135 +18,140 @@ pub fn blockhash<I: Image, B: HashBytes>(img: &I, width: u32, height: u32) -> B
 
     // Skip the floating point math if it's unnecessary
     if iwidth % width == 0 && iheight % height == 0 {
-        blockhash_fast(img, width, height)
+        blockhash_quick(img, width, height)
     } else {
-        blockhash_slow(img, width, height)
-    }        
-} 
-
-macro_rules! gen_hash {
-    ($imgty:ty, $valty:ty, $blocks: expr, $width:expr, $block_width:expr, $block_height:expr, $eq_fn:expr) => ({
-        #[allow(deprecated)] // deprecated as of 0.22
-        let channel_count = <<$imgty as GenericImageView>::Pixel as Pixel>::channel_count() as u32;
-
-        let group_len = ($width * 4) as usize;
-
-        let block_area = $block_width * $block_height;
-
-        let cmp_factor = match channel_count {
-            3 | 4 => 255u32 as $valty * 3u32 as $valty,
-            2 | 1 => 255u32 as $valty,
-            _ => panic!("Unrecognized channel count from Image: {}", channel_count),
-        }  
-            * block_area 
-            / (2u32 as $valty);
-
-        let medians: Vec<$valty> = $blocks.chunks(group_len).map(get_median).collect();
-
-        BitSet::from_bools(
-            $blocks.chunks(group_len).zip(medians)
-            .flat_map(|(blocks, median)| 
-                blocks.iter().map(move |&block| 
-                    block > median ||
-                        ($eq_fn(block, median) && median > cmp_factor)
-                )
-            )
-        )
-    })
-}
-
-fn block_adder<'a, T: AddAssign + 'a>(blocks: &'a mut [T], width: u32) -> impl FnMut(u32, u32, T) + 'a {
-    move |x, y, add| (blocks[(y as usize) * (width as usize) + (x as usize)] += add)
+        blockhash_precise(img, width, height)
+    }
 }
 
-fn blockhash_slow<I: Image, B: HashBytes>(img: &I, hwidth: u32, hheight: u32) -> B {
-    let mut blocks = vec![0f32; (hwidth * hheight) as usize];
+fn blockhash_precise<I: Image, B: HashBytes>(img: &I, hwidth: u32, hheight: u32) -> B {
+    let mut blocks = vec![0f64; (hwidth * hheight) as usize];
 
     let (iwidth, iheight) = img.dimensions();
-    
-    // Block dimensions, in pixels
-    let (block_width, block_height) = (iwidth as f32 / hwidth as f32, iheight as f32 / hheight as f32);
-
-    img.foreach_pixel8(|x, y, px| {
-        let mut add_to_block = block_adder(&mut blocks, hwidth);
 
-        let px_sum = sum_px(px) as f32;
+    // Block dimensions, in pixels
+    let (block_width, block_height) = (iwidth as f64 / hwidth as f64, iheight as f64 / hheight as f64);
 
-        let (x, y) = (x as f32, y as f32);
+    // the reference finds the weights and blocks for each row and column the same way
+    let weights = |pos: u32, block_size: f64, len: u32| {
+        let pos_mod = (pos as f64 + 1.) % block_size;
+        let (frac, int) = (pos_mod.fract(), pos_mod.trunc());
 
-        let block_x = x / block_width;
-        let block_y = y / block_height;
+        let first = (pos as f64 / block_size).floor() as u32;
 
-        let x_mod = x + 1. % block_width;
-        let y_mod = y + 1. % block_height;
+        // `int` will be 0 on the bottom/right borders and on block boundaries
+        let second = if int > 0. || pos + 1 == len {
+            first
+        } else {
+            (pos as f64 / block_size).ceil() as u32
+        };
 
-        // terminology is mostly arbitrary as long as we're consistent
-        // if `x` evenly divides `block_height`, this weight will be 0
-        // so we don't double the sum as `block_top` will equal `block_bottom`
-        let weight_left = x_mod.fract();
-        let weight_right = 1. - weight_left;
-        let weight_top = y_mod.fract();
-        let weight_bottom = 1. - weight_top;
+        (first, second, 1. - frac, frac)
+    };
 
-        let block_left = block_x.floor() as u32;
-        let block_top = block_y.floor() as u32;
+    img.foreach_pixel8(|x, y, px| {
+        let px_sum = sum_px(px) as f64;
 
-        let block_right = if x_mod.trunc() == 0. {
-            block_x.ceil() as u32
-        } else {
-            block_left
-        };
+        let (block_top, block_bottom, weight_top, weight_bottom) = weights(y, block_height, iheight);
+        let (block_left, block_right, weight_left, weight_right) = weights(x, block_width, iwidth);
 
-        let block_bottom = if y_mod.trunc() == 0. {
-            block_y.ceil() as u32
-        } else {
-            block_top
+        let mut add_to_block = |x: u32, y: u32, add: f64| {
+            blocks[(y * hwidth + x) as usize] += add;
         };
 
-        add_to_block(block_left, block_top, px_sum * weight_left * weight_top);
-        add_to_block(block_left, block_bottom, px_sum * weight_left * weight_bottom);
-        add_to_block(block_right, block_top, px_sum * weight_right * weight_top);
-        add_to_block(block_right, block_bottom, px_sum * weight_right * weight_bottom);
+        add_to_block(block_left, block_top, px_sum * weight_top * weight_left);
+        add_to_block(block_right, block_top, px_sum * weight_top * weight_right);
+        add_to_block(block_left, block_bottom, px_sum * weight_bottom * weight_left);
+        add_to_block(block_right, block_bottom, px_sum * weight_bottom * weight_right);
     });
 
-    
-    gen_hash!(I, f32, blocks, hwidth, block_width, block_height,
-        |l: f32, r: f32| (l - r).abs() < FLOAT_EQ_MARGIN)
+    translate_blocks_to_bits(&blocks, block_width * block_height)
 }
 
-fn blockhash_fast<I: Image, B: HashBytes>(img: &I, hwidth: u32, hheight: u32) -> B {
+/// The "quick" method, which divides the image into blocks of whole pixels.
+///
+/// If the image dimensions are not multiples of the hash dimensions then the leftover pixels
+/// on the right and bottom edges are ignored. Like the reference implementation, an image
+/// narrower or shorter than the hash has empty blocks and hashes to all zeroes.
+pub fn blockhash_quick<I: Image, B: HashBytes>(img: &I, hwidth: u32, hheight: u32) -> B {
+    assert_eq!(hwidth % 4, 0, "width must be multiple of 4");
+    assert_eq!(hheight % 4, 0, "height must be multiple of 4");
+
     let mut blocks = vec![0u32; (hwidth * hheight) as usize];
     let (iwidth, iheight) = img.dimensions();
 
     let (block_width, block_height) = (iwidth / hwidth, iheight / hheight);
 
-    img.foreach_pixel8(|x, y, px| {
-        let mut add_to_block = block_adder(&mut blocks, hwidth);
-
-        let px_sum = sum_px(px);
+    if block_width > 0 && block_height > 0 {
+        img.foreach_pixel8(|x, y, px| {
+            let block_x = x / block_width;
+            let block_y = y / block_height;
 
-        let block_x = x / block_width;
-        let block_y = y / block_height;
+            if block_x < hwidth && block_y < hheight {
+                blocks[(block_y * hwidth + block_x) as usize] += sum_px(px);
+            }
+        });
+    }
 
-        add_to_block(block_x, block_y, px_sum);
-    });
+    let blocks: Vec<f64> = blocks.into_iter().map(|x| x as f64).collect();
+    translate_blocks_to_bits(&blocks, (block_width * block_height) as f64)
+}
 
-    gen_hash!(I, u32, blocks, hwidth, block_width, block_height, |l, r| l == r)
+/// Compare the blocks to the median of each of four horizontal bands.
+///
+/// The bits are packed most-significant first so the hexadecimal representation of the hash
+/// matches that of the reference implementation.
+fn translate_blocks_to_bits<B: HashBytes>(blocks: &[f64], pixels_per_block: f64) -> B {
+    let half_block_value = pixels_per_block * 256. * 3. / 2.;
+
+    let bits: Vec<bool> = blocks.chunks(blocks.len() / 4).flat_map(|band| {
+        let median = get_median_avg(band);
+
+        // With images dominated by black or white, the median may end up being 0 or the max
+        // value, and thus having a lot of blocks of value equal to the median. To avoid
+        // generating hashes of all zeros or ones, in that case output 0 if the median is in
+        // the lower value space, 1 otherwise
+        band.iter().map(move |&block| {
+            block > median || ((block - median).abs() < 1. && median > half_block_value)
+        })
+    }).collect();
+
+    B::from_iter(bits.chunks(8).map(|byte| {
+        byte.iter().enumerate().fold(0u8, |accum, (n, &bit)| accum | ((bit as u8) << (7 - n)))
+    }))
 }
 
+/// Sum the RGB channels of the pixel, treating fully transparent pixels as white.
+///
+/// Grayscale pixels are counted as if they were converted to RGB first.
 #[inline(always)]
 fn sum_px(chans: &[u8]) -> u32 {
     // Branch prediction should eliminate the match after a few iterations
     match chans.len() {
         4 => if chans[3] == 0 { 255 * 3 } else { sum_px(&chans[..3]) },
         3 => chans.iter().map(|&x| x as u32).sum(),
-        2 => if chans[1] == 0 { 255 } else { chans[0] as u32 },
-        1 => chans[0] as u32,
+        2 => if chans[1] == 0 { 255 * 3 } else { sum_px(&chans[..1]) },
+        1 => chans[0] as u32 * 3,
         channels => panic!("Unsupported channel count in image: {}", channels),
     }
 }
 
-fn get_median<T: PartialOrd + Copy>(data: &[T]) -> T {
+/// The median of `data`, averaging the two middle values if the length is even.
+pub fn get_median_avg<T: PartialOrd + Copy + Into<f64>>(data: &[T]) -> f64 {
+    let mut scratch = data.to_owned();
+    let mid = scratch.len() / 2;
+    let upper = (*qselect_inplace(&mut scratch, mid)).into();
+
+    if mid > 0 && mid * 2 == scratch.len() {
+        // quickselect leaves only values no greater than the median before it
+        let lower = scratch[..mid].iter().map(|&x| x.into()).fold(f64::MIN, f64::max);
+        (upper + lower) / 2.
+    } else {
+        upper
+    }
+}
+
+pub fn get_median<T: PartialOrd + Copy>(data: &[T]) -> T {
     let mut scratch = data.to_owned();
     let median = scratch.len() / 2;
     *qselect_inplace(&mut scratch, median)
@@ -155,7 +159,7 @@ fn get_median<T: PartialOrd + Copy>(data: &[T]) -> T {
 
 const SORT_THRESH: usize = 8;
 
-fn qselect_inplace<T: PartialOrd>(data: &mut [T], k: usize) -> &mut T {
+pub fn qselect_inplace<T: PartialOrd>(data: &mut [T], k: usize) -> &mut T {
     let len = data.len();
 
     assert!(k < len, "Called qselect_inplace with k = {} and data length: {}", k, len);
@@ -192,7 +196,7 @@ fn partition<T: PartialOrd>(data: &mut [T]) -> usize {
     let mut curr = 0;
 
     for i in 0 .. len - 1 {
-        if &data[i] < &data[len - 1] {
+        if data[i] < data[len - 1] {
             data.swap(i, curr);
             curr += 1;
         }
@@ -218,3 +222,31 @@ fn median_of_3<T: PartialOrd>(mut x: T, mut y: T, mut z: T) -> T {
 
     y
 }
+
+#[test]
+fn test_reference_output() {
+    use image::{Rgba, RgbaImage};
+
+    // expected values were produced by the Python reference implementation
+    fn hex_to_bytes(hex: &str) -> Vec<u8> {
+        (0 .. hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i .. i + 2], 16).unwrap()).collect()
+    }
+
+    let img = RgbaImage::from_fn(37, 23, |x, y| Rgba([
+        ((x * 7 + y * 3) % 256) as u8,
+        (x * y % 256) as u8,
+        ((x * 13) ^ (y * 5)) as u8,
+        if (x + y) % 11 == 0 { 0 } else { 255 },
+    ]));
+
+    let quick: Vec<u8> = blockhash_quick(&img, 16, 16);
+    assert_eq!(quick, hex_to_bytes("84d305e709ef09cf10c711cf21cf23df41cf43cf03d985f107e10fe30fc317c3"));
+
+    let precise: Vec<u8> = blockhash(&img, 16, 16);
+    assert_eq!(precise, hex_to_bytes("094709cf139f03bf031f039f07bd07e60fc60f8e178e0f3c0f384ef10de38de2"));
+
+    // the reference's blocks are zero pixels wide, so every bit is zero
+    let small = image::imageops::crop_imm(&img, 0, 0, 12, 23).to_image();
+    let quick: Vec<u8> = blockhash_quick(&small, 16, 16);
+    assert_eq!(quick, vec![0; 32]);
+}
@@ -0,0 +1,82 @@
+// Color moments as described by Stricker and Orengo, "Similarity of Color Images" (1995)
+use {FloatHash, Image};
+
+use super::color_space::{rgb_to_hsv, rgb_to_ycbcr};
+use super::dihedral::px_to_rgba;
+
+/// Number of channels across both color spaces.
+const NUM_CHANNELS: usize = 6;
+
+impl FloatHash {
+    /// Calculate the color moment hash of an image.
+    ///
+    /// Unlike the algorithms in [`HashAlg`](enum.HashAlg.html) this does not discard color, so
+    /// recolored images produce different hashes.
+    ///
+    /// Each pixel is converted to the HSV and YCbCr color spaces with all channels in `[0, 1]`
+    /// and the mean, standard deviation and skewness (the cube root of the third central moment)
+    /// of each channel are taken, producing 18 features in the order
+    /// `H, S, V, Y, Cb, Cr`, with the three moments of each channel in turn.
+    ///
+    /// The alpha channel is ignored. Grayscale images have no hue or saturation so the
+    /// hashes of recolored grayscale images should be compared with care.
+    pub fn color_moments<I: Image>(img: &I) -> FloatHash {
+        // raw moments `E[x], E[x^2], E[x^3]` per channel
+        let mut sums = [[0f64; 3]; NUM_CHANNELS];
+        let mut count = 0u64;
+
+        img.foreach_pixel8(|_, _, px| {
+            let [r, g, b, _] = px_to_rgba(px);
+
+            let (r, g, b) = (r as f64 / 255., g as f64 / 255., b as f64 / 255.);
+
+            let (h, s, v) = rgb_to_hsv(r, g, b);
+            let (y, cb, cr) = rgb_to_ycbcr(r, g, b);
+
+            for (sum, &val) in sums.iter_mut().zip(&[h, s, v, y, cb, cr]) {
+                sum[0] += val;
+                sum[1] += val * val;
+                sum[2] += val * val * val;
+            }
+
+            count += 1;
+        });
+
+        let count = count.max(1) as f64;
+
+        let features: Vec<f32> = sums.iter().flat_map(|sum| {
+            let mean = sum[0] / count;
+            let (sq_mean, cube_mean) = (sum[1] / count, sum[2] / count);
+
+            let variance = (sq_mean - mean * mean).max(0.);
+            let third_central = cube_mean - 3. * mean * sq_mean + 2. * mean * mean * mean;
+
+            vec![mean as f32, variance.sqrt() as f32, third_central.cbrt() as f32]
+        }).collect();
+
+        FloatHash::from_floats(&features)
+    }
+}
+
+#[test]
+fn test_color_moments() {
+    use image::{Rgb, RgbImage};
+
+    let img = RgbImage::from_fn(16, 16, |x, _| if x < 8 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) });
+    let hash = FloatHash::color_moments(&img);
+    let features = hash.as_floats();
+
+    assert_eq!(features.len(), 18);
+    // half red (H = 0) and half blue (H = 2/3)
+    assert!((features[0] - 1. / 3.).abs() < 0.0001);
+    assert!((features[1] - 1. / 3.).abs() < 0.0001);
+    // symmetric distribution
+    assert!(features[2].abs() < 0.001);
+
+    // swapping the colors changes the hue but not its moments
+    let swapped = RgbImage::from_fn(16, 16, |x, _| if x < 8 { Rgb([0, 0, 255]) } else { Rgb([255, 0, 0]) });
+    assert!(hash.dist(&FloatHash::color_moments(&swapped)) < 0.001);
+
+    let recolored = RgbImage::from_fn(16, 16, |x, _| if x < 8 { Rgb([0, 255, 0]) } else { Rgb([0, 0, 255]) });
+    assert!(hash.dist(&FloatHash::color_moments(&recolored)) > 0.1);
+}
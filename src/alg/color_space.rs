@@ -0,0 +1,260 @@
+use std::borrow::Cow;
+
+use image::GrayImage;
+
+use Image;
+
+use super::dihedral::px_to_rgba;
+
+/// Color spaces that images can be hashed in, set with
+/// [`HasherConfig::color_space()`](struct.HasherConfig.html#method.color_space).
+///
+/// With any color space besides `Luma`, the configured algorithm is run separately on each
+/// channel and the hashes are concatenated in channel order. The alpha channel is ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorSpace {
+    /// The image is converted to grayscale as usual, producing a single hash.
+    Luma,
+    /// The red, green and blue channels are hashed separately.
+    Rgb,
+    /// The hue, saturation and value channels are hashed separately.
+    ///
+    /// The hue is scaled to the full range of a channel; it wraps around so colors near red
+    /// may land at either end.
+    Hsv,
+    /// The luma, blue-difference and red-difference channels (full-range BT.601) are hashed
+    /// separately.
+    YCbCr,
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::Luma
+    }
+}
+
+impl ColorSpace {
+    /// The number of channels, and thus sub-hashes, for this color space.
+    pub fn channels(&self) -> usize {
+        match *self {
+            ColorSpace::Luma => 1,
+            _ => 3,
+        }
+    }
+
+    /// Split the image into the three channels of this color space.
+    ///
+    /// ### Panics
+    /// If this is `ColorSpace::Luma`.
+    pub(crate) fn split_channels<I: Image>(&self, img: &I) -> [GrayImage; 3] {
+        let (width, height) = img.dimensions();
+
+        let mut channels = [
+            GrayImage::new(width, height),
+            GrayImage::new(width, height),
+            GrayImage::new(width, height),
+        ];
+
+        img.foreach_pixel8(|x, y, px| {
+            let [r, g, b, _] = px_to_rgba(px);
+
+            let vals = match *self {
+                ColorSpace::Rgb => [r, g, b],
+                ColorSpace::Hsv => to_bytes(rgb_to_hsv(to_float(r), to_float(g), to_float(b))),
+                ColorSpace::YCbCr => to_bytes(rgb_to_ycbcr(to_float(r), to_float(g), to_float(b))),
+                ColorSpace::Luma => panic!("grayscale images are not split into channels"),
+            };
+
+            for (chan, &val) in channels.iter_mut().zip(&vals) {
+                chan.put_pixel(x, y, [val].into());
+            }
+        });
+
+        channels
+    }
+}
+
+/// Methods of converting images to grayscale, set with
+/// [`HasherConfig::grayscale()`](struct.HasherConfig.html#method.grayscale).
+///
+/// Images that are already grayscale are used as-is by every method. The alpha channel is
+/// ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Grayscale {
+    /// The conversion provided by the `image` crate, which weights the gamma-encoded channels
+    /// like Rec. 709 and truncates the result.
+    Image,
+    /// Rec. 601 weights on the gamma-encoded channels: `0.299 R + 0.587 G + 0.114 B`.
+    Rec601,
+    /// Rec. 709 weights on the gamma-encoded channels: `0.2126 R + 0.7152 G + 0.0722 B`.
+    Rec709,
+    /// Rec. 709 weights on the linear-light values, decoding the channels from sRGB first and
+    /// encoding the resulting luminance back to sRGB.
+    ///
+    /// This is the luminance that the image actually displays, so it's less affected by gamma
+    /// adjustments which change the relative brightness of the channels.
+    LinearLight,
+    /// The mean of the red, green and blue channels.
+    Average,
+    /// Just the red channel.
+    Red,
+    /// Just the green channel.
+    Green,
+    /// Just the blue channel.
+    Blue,
+}
+
+impl Default for Grayscale {
+    fn default() -> Self {
+        Grayscale::Image
+    }
+}
+
+impl Grayscale {
+    /// Convert the image to grayscale with this method.
+    pub(crate) fn convert<'a, I: Image>(&self, img: &'a I) -> Cow<'a, GrayImage> {
+        if *self == Grayscale::Image {
+            return img.to_grayscale();
+        }
+
+        let to_linear = srgb_to_linear_table();
+
+        let (width, height) = img.dimensions();
+        let mut gray = GrayImage::new(width, height);
+
+        img.foreach_pixel8(|x, y, px| {
+            // grayscale pixels are used as-is
+            if let 1 | 2 = px.len() {
+                gray.put_pixel(x, y, [px[0]].into());
+                return;
+            }
+
+            let [r, g, b, _] = px_to_rgba(px);
+
+            let weighted = |[wr, wg, wb]: [f64; 3]| {
+                wr * r as f64 + wg * g as f64 + wb * b as f64
+            };
+
+            let val = match *self {
+                Grayscale::Rec601 => weighted([0.299, 0.587, 0.114]),
+                Grayscale::Rec709 => weighted(REC709_WEIGHTS),
+                Grayscale::LinearLight => {
+                    let luminance = REC709_WEIGHTS[0] * to_linear[r as usize]
+                        + REC709_WEIGHTS[1] * to_linear[g as usize]
+                        + REC709_WEIGHTS[2] * to_linear[b as usize];
+                    linear_to_srgb(luminance) * 255.
+                },
+                Grayscale::Average => (r as f64 + g as f64 + b as f64) / 3.,
+                Grayscale::Red => r as f64,
+                Grayscale::Green => g as f64,
+                Grayscale::Blue => b as f64,
+                Grayscale::Image => unreachable!(),
+            };
+
+            gray.put_pixel(x, y, [val.round().clamp(0., 255.) as u8].into());
+        });
+
+        Cow::Owned(gray)
+    }
+}
+
+const REC709_WEIGHTS: [f64; 3] = [0.2126, 0.7152, 0.0722];
+
+/// The linear-light value in `[0, 1]` of each sRGB-encoded byte.
+fn srgb_to_linear_table() -> [f64; 256] {
+    let mut table = [0.; 256];
+
+    for (i, out) in table.iter_mut().enumerate() {
+        let x = i as f64 / 255.;
+        *out = if x <= 0.04045 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) };
+    }
+
+    table
+}
+
+fn linear_to_srgb(x: f64) -> f64 {
+    if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1. / 2.4) - 0.055 }
+}
+
+fn to_float(x: u8) -> f64 {
+    x as f64 / 255.
+}
+
+fn to_bytes((a, b, c): (f64, f64, f64)) -> [u8; 3] {
+    let to_byte = |x: f64| (x * 255.).round().clamp(0., 255.) as u8;
+    [to_byte(a), to_byte(b), to_byte(c)]
+}
+
+/// Convert RGB to HSV, with hue normalized to `[0, 1)`.
+pub fn rgb_to_hsv(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let hue = if chroma == 0. {
+        0.
+    } else if max == r {
+        ((g - b) / chroma).rem_euclid(6.)
+    } else if max == g {
+        (b - r) / chroma + 2.
+    } else {
+        (r - g) / chroma + 4.
+    };
+
+    let sat = if max == 0. { 0. } else { chroma / max };
+
+    (hue / 6., sat, max)
+}
+
+/// Convert RGB to full-range BT.601 YCbCr, with the chroma channels offset to `[0, 1]`.
+pub fn rgb_to_ycbcr(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let cb = 0.5 + (b - y) * 0.564;
+    let cr = 0.5 + (r - y) * 0.713;
+
+    (y, cb, cr)
+}
+
+#[test]
+fn test_channel_swap() {
+    use image::{Rgb, RgbImage};
+    use HasherConfig;
+
+    let img = RgbImage::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, 128, (y * 8) as u8]));
+    let swapped = RgbImage::from_fn(32, 32, |x, y| Rgb([(y * 8) as u8, 128, (x * 8) as u8]));
+
+    let hasher = HasherConfig::new().color_space(ColorSpace::Rgb).to_hasher();
+    let (hash, swapped_hash) = (hasher.hash_image(&img), hasher.hash_image(&swapped));
+
+    assert_eq!(hash.as_bytes().len(), 3 * 8);
+
+    let dists = hasher.channel_dists(&hash, &swapped_hash);
+    assert_eq!(dists.len(), 3);
+    assert!(dists[0] > 0 && dists[2] > 0);
+    assert_eq!(dists[1], 0);
+    assert_eq!(dists.iter().sum::<u32>(), hash.dist(&swapped_hash));
+}
+
+#[test]
+fn test_grayscale_methods() {
+    use image::{Rgb, RgbImage};
+    use HasherConfig;
+
+    let img = RgbImage::from_pixel(1, 1, Rgb([200, 100, 50]));
+    let convert = |method: Grayscale| method.convert(&img).get_pixel(0, 0)[0];
+
+    assert_eq!(convert(Grayscale::Image), 117);
+    assert_eq!(convert(Grayscale::Rec601), 124);
+    assert_eq!(convert(Grayscale::Rec709), 118);
+    assert_eq!(convert(Grayscale::LinearLight), 128);
+    assert_eq!(convert(Grayscale::Average), 117);
+    assert_eq!(convert(Grayscale::Blue), 50);
+
+    // hashing a single channel is the same as hashing that channel as a grayscale image
+    let img = RgbImage::from_fn(32, 32, |x, _| Rgb([(x * 8) as u8, 255 - (x * 8) as u8, 128]));
+    let red = GrayImage::from_fn(32, 32, |x, _| [(x * 8) as u8].into());
+
+    let hasher = HasherConfig::new().grayscale(Grayscale::Red).to_hasher();
+    assert_eq!(hasher.hash_image(&img), hasher.hash_image(&red));
+    assert_ne!(hasher.hash_image(&img), HasherConfig::new().to_hasher().hash_image(&img));
+}
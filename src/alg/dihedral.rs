@@ -0,0 +1,168 @@
+use image::{imageops, ImageBuffer, Pixel, Rgba, RgbaImage};
+
+use {HashBytes, HashCtxt, HashVals, Image, ImageHash};
+
+use super::HashAlg;
+
+/// The number of elements of the dihedral group of the square.
+const NUM_TRANSFORMS: usize = 8;
+
+/// The hashes of all eight rotations and reflections (the dihedral group) of an image.
+///
+/// Get an instance with
+/// [`Hasher::hash_image_dihedral()`](struct.Hasher.html#method.hash_image_dihedral).
+///
+/// The hashes are in the order: original, rotated 90, 180 and 270 degrees clockwise,
+/// mirrored horizontally, flipped vertically, transposed (mirrored across the main diagonal)
+/// and anti-transposed (mirrored across the other diagonal).
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct DihedralHashes<B = Box<[u8]>> {
+    hashes: Vec<ImageHash<B>>,
+}
+
+impl<B: HashBytes> DihedralHashes<B> {
+    /// Get the hashes of all eight transforms.
+    pub fn as_slice(&self) -> &[ImageHash<B>] { &self.hashes }
+
+    /// Get the canonical hash of the image: the hash of the transform with the
+    /// lexicographically smallest bytes.
+    ///
+    /// Rotated and mirrored copies of an image have the same canonical hash as long as their
+    /// hashes are otherwise identical. Because slightly different images may pick different
+    /// transforms, prefer [`dist()`](#method.dist) when comparing against a stored hash.
+    pub fn canonical(&self) -> &ImageHash<B> {
+        &self.hashes[self.canonical_index()]
+    }
+
+    /// Take the canonical hash out of this set; see [`canonical()`](#method.canonical).
+    pub fn into_canonical(mut self) -> ImageHash<B> {
+        let index = self.canonical_index();
+        self.hashes.swap_remove(index)
+    }
+
+    fn canonical_index(&self) -> usize {
+        (0 .. self.hashes.len()).min_by(|&l, &r| {
+            self.hashes[l].as_bytes().cmp(self.hashes[r].as_bytes())
+        }).expect("no hashes")
+    }
+
+    /// Calculate the minimum Hamming distance between any of these hashes and `other`.
+    ///
+    /// `other` should be a plain hash of an image from the same hasher.
+    pub fn dist(&self, other: &ImageHash<B>) -> u32 {
+        self.hashes.iter().map(|hash| hash.dist(other)).min().expect("no hashes")
+    }
+
+    pub(crate) fn from_hashes<I: Iterator<Item = B>>(hashes: I) -> Self {
+        DihedralHashes {
+            hashes: hashes.map(|hash| ImageHash { hash, __backcompat: () }).collect(),
+        }
+    }
+}
+
+/// Hash all the transforms of the image by transforming the resized grayscale image, for
+/// the algorithms where `hashes_resized()` is true and no DCT preprocessing is configured.
+///
+/// The image only needs to be resized once more for the transforms that swap its dimensions,
+/// and only if the resize dimensions aren't square.
+pub fn hash_resized<I: Image, B: HashBytes>(alg: HashAlg, ctxt: &HashCtxt, img: &I)
+        -> impl Iterator<Item = B> {
+    let post_gauss = ctxt.gauss_preproc(img);
+    let grayscale = ctxt.gray_preproc(post_gauss.to_grayscale(&ctxt.grayscale));
+
+    let (width, height) = alg.resize_dimensions(ctxt.width, ctxt.height);
+
+    let resized = imageops::resize(&*grayscale, width, height, ctxt.resize_filter);
+
+    let transposed = if width == height {
+        None
+    } else {
+        Some(imageops::resize(&*grayscale, height, width, ctxt.resize_filter))
+    };
+
+    (0 .. NUM_TRANSFORMS).map(move |n| {
+        let src = match transposed {
+            Some(ref transposed) if swaps_dimensions(n) => transposed,
+            _ => &resized,
+        };
+
+        alg.hash_vals(HashVals::Bytes(transform(src, n).into_vec()), width as usize)
+    })
+}
+
+/// Apply the `n`th transform, in the order documented on `DihedralHashes`.
+pub fn transform<P>(img: &ImageBuffer<P, Vec<u8>>, n: usize) -> ImageBuffer<P, Vec<u8>>
+where P: Pixel<Subpixel = u8> + 'static {
+    match n {
+        0 => img.clone(),
+        1 => imageops::rotate90(img),
+        2 => imageops::rotate180(img),
+        3 => imageops::rotate270(img),
+        4 => imageops::flip_horizontal(img),
+        5 => imageops::flip_vertical(img),
+        6 => imageops::flip_horizontal(&imageops::rotate90(img)),
+        7 => imageops::flip_vertical(&imageops::rotate90(img)),
+        _ => panic!("no dihedral transform {}", n),
+    }
+}
+
+/// The transforms which swap the width and height of the image.
+fn swaps_dimensions(n: usize) -> bool {
+    matches!(n, 1 | 3 | 6 | 7)
+}
+
+/// Copy an image of any supported channel count into RGBA so it can be transformed.
+pub fn to_rgba<I: Image>(img: &I) -> RgbaImage {
+    let (width, height) = img.dimensions();
+    let mut rgba = RgbaImage::new(width, height);
+
+    img.foreach_pixel8(|x, y, px| rgba.put_pixel(x, y, Rgba(px_to_rgba(px))));
+
+    rgba
+}
+
+/// Expand a pixel from [`Image::foreach_pixel8()`](trait.Image.html#tymethod.foreach_pixel8)
+/// to RGBA, copying luma to the color channels and filling in an opaque alpha channel.
+///
+/// ### Panics
+/// If the pixel has more than 4 channels.
+pub fn px_to_rgba(px: &[u8]) -> [u8; 4] {
+    match *px {
+        [r, g, b, a] => [r, g, b, a],
+        [r, g, b] => [r, g, b, 255],
+        [l, a] => [l, l, l, a],
+        [l] => [l, l, l, 255],
+        _ => panic!("Unsupported channel count in image: {}", px.len()),
+    }
+}
+
+#[test]
+fn test_dihedral_hashes() {
+    use image::{GrayImage, Luma};
+    use HasherConfig;
+
+    let img = GrayImage::from_fn(96, 64, |x, y| Luma([(x * x / 60 + y) as u8]));
+    let rotated = imageops::rotate90(&img);
+    let mirrored = imageops::flip_horizontal(&img);
+
+    // resized-grid transforms are not exactly the same as transforming the image first
+    for &alg in &[HashAlg::Mean, HashAlg::Gradient, HashAlg::DoubleGradient] {
+        let hasher = HasherConfig::new().hash_alg(alg).to_hasher();
+        let hashes = hasher.hash_image_dihedral(&img);
+
+        assert_eq!(hashes.as_slice()[0], hasher.hash_image(&img));
+        assert!(hashes.dist(&hasher.hash_image(&rotated)) <= 2, "{:?}", alg);
+        assert!(hashes.dist(&hasher.hash_image(&mirrored)) <= 2, "{:?}", alg);
+    }
+
+    // the other algorithms transform the image itself, so they match exactly
+    let hasher = HasherConfig::new().hash_alg(HashAlg::Blockhash).hash_size(16, 16).to_hasher();
+    let hashes = hasher.hash_image_dihedral(&img);
+
+    assert_eq!(hashes.dist(&hasher.hash_image(&rotated)), 0);
+    assert_eq!(hashes.canonical(), hasher.hash_image_dihedral(&mirrored).canonical());
+
+    let canonical = HasherConfig::new().hash_alg(HashAlg::Blockhash).hash_size(16, 16)
+        .dihedral_invariant().to_hasher();
+    assert_eq!(canonical.hash_image(&rotated), *hashes.canonical());
+}
@@ -0,0 +1,169 @@
+// Based on `ph_mh_imagehash()` from pHash: https://www.phash.org/
+use image::{imageops, GrayImage};
+
+use Image;
+use FilterType;
+
+/// The image is scaled to this size before filtering.
+const RESIZE_DIMS: u32 = 512;
+/// Size of the blocks that the filter response is summed over.
+const BLOCK_SIZE: usize = 16;
+/// Number of blocks per row and column; the blocks don't quite cover the image.
+const NUM_BLOCKS: usize = 31;
+/// Stride between the 3 x 3 groups of blocks that each produce 9 bits of the hash.
+const GROUP_STRIDE: usize = 4;
+
+/// The largest kernel radius allowed, a quarter of the side of the resized image.
+const MAX_RADIUS: u32 = 128;
+
+/// The side length of the hash, in bits (`8 * 8` groups of `3 * 3` bits each).
+pub const HASH_SIDE: u32 = 24;
+
+/// A Mexican hat (Laplacian of Gaussian) kernel.
+pub struct MhKernel {
+    kernel: Vec<f32>,
+    radius: usize,
+}
+
+impl MhKernel {
+    /// Build the kernel with the given scale parameters, as in pHash.
+    ///
+    /// ### Panics
+    /// If `alpha` is not positive or the radius, `4 * alpha^level`, is not in `1 ..= 128`.
+    pub fn new(alpha: f32, level: f32) -> Self {
+        let radius = 4. * alpha.powf(level);
+
+        assert!(alpha > 0. && radius >= 1. && radius <= MAX_RADIUS as f32,
+                "Marr-Hildreth kernel radius must be in 1 ..= {} with a positive alpha, \
+                 got 4 * {}^{} = {}", MAX_RADIUS, alpha, level, radius);
+
+        let radius = radius as usize;
+        let side = radius * 2 + 1;
+        let scale = alpha.powf(-level);
+
+        let kernel = (0 .. side * side).map(|i| {
+            let x = scale * ((i % side) as f32 - radius as f32);
+            let y = scale * ((i / side) as f32 - radius as f32);
+            let a = x * x + y * y;
+            (2. - a) * (-a / 2.).exp()
+        }).collect();
+
+        MhKernel { kernel, radius }
+    }
+
+    /// Correlate the kernel with `img` at `(x, y)`, clamping coordinates to the image edges.
+    fn correlate_at(&self, img: &GrayImage, x: usize, y: usize) -> f32 {
+        let (width, height) = (img.width() as usize, img.height() as usize);
+        let side = self.radius * 2 + 1;
+        let pixels: &[u8] = img;
+
+        self.kernel.chunks(side).enumerate().map(|(ky, row)| {
+            let iy = (y + ky).saturating_sub(self.radius).min(height - 1);
+
+            row.iter().enumerate().map(|(kx, &k)| {
+                let ix = (x + kx).saturating_sub(self.radius).min(width - 1);
+                k * pixels[iy * width + ix] as f32
+            }).sum::<f32>()
+        }).sum()
+    }
+}
+
+/// Calculate the bits of the Marr-Hildreth hash, in row-major order of the 3 x 3 groups.
+pub fn mh_hash(img: &GrayImage, kernel: &MhKernel, filter: FilterType) -> Vec<bool> {
+    let img = img.blur(1.0);
+    let mut img = imageops::resize(&img, RESIZE_DIMS, RESIZE_DIMS, filter);
+    equalize(&mut img);
+
+    // pHash normalizes the response to `[0, 1]` first but the comparisons to the mean of each
+    // group are invariant to that, so we skip it along with the pixels not covered by the blocks
+    let mut blocks = vec![0f32; NUM_BLOCKS * NUM_BLOCKS];
+
+    for y in 0 .. NUM_BLOCKS * BLOCK_SIZE {
+        for x in 0 .. NUM_BLOCKS * BLOCK_SIZE {
+            blocks[(y / BLOCK_SIZE) * NUM_BLOCKS + x / BLOCK_SIZE] +=
+                kernel.correlate_at(&img, x, y);
+        }
+    }
+
+    let mut bits = Vec::with_capacity((HASH_SIDE * HASH_SIDE) as usize);
+
+    for group_y in (0 .. NUM_BLOCKS - 2).step_by(GROUP_STRIDE) {
+        for group_x in (0 .. NUM_BLOCKS - 2).step_by(GROUP_STRIDE) {
+            let group: Vec<f32> = blocks[group_y * NUM_BLOCKS ..].chunks(NUM_BLOCKS).take(3)
+                .flat_map(|row| row[group_x .. group_x + 3].iter().cloned())
+                .collect();
+
+            let mean = group.iter().sum::<f32>() / group.len() as f32;
+            bits.extend(group.into_iter().map(|block| block > mean));
+        }
+    }
+
+    bits
+}
+
+/// Equalize the histogram of the image in-place.
+fn equalize(img: &mut GrayImage) {
+    let (min, max) = img.iter().fold((255u8, 0u8), |(min, max), &x| (min.min(x), max.max(x)));
+
+    if min == max {
+        return;
+    }
+
+    let mut cumulative = [0u32; 256];
+    img.iter().for_each(|&x| cumulative[x as usize] += 1);
+
+    for i in 1 .. 256 {
+        cumulative[i] += cumulative[i - 1];
+    }
+
+    let len = img.len() as f32;
+    let range = (max - min) as f32;
+
+    img.iter_mut().for_each(|x| {
+        *x = (min as f32 + range * cumulative[*x as usize] as f32 / len) as u8;
+    });
+}
+
+#[test]
+fn test_mh_kernel() {
+    let kernel = MhKernel::new(2., 1.);
+    assert_eq!(kernel.radius, 8);
+    assert_eq!(kernel.kernel.len(), 17 * 17);
+    // the center of a Mexican hat is its peak
+    assert_eq!(kernel.kernel[8 * 17 + 8], 2.);
+}
+
+#[test]
+fn test_mh_hash() {
+    use image::Luma;
+    use {HashAlg, HasherConfig};
+
+    // edges everywhere, since flat areas have no response to compare
+    let img = GrayImage::from_fn(128, 128, |x, y| {
+        let (x, y) = (x as f32, y as f32);
+        Luma([(128. + 60. * (x / 5.).sin() + 40. * (y / 7. + x / 11.).cos()) as u8])
+    });
+    // brighter and with less contrast
+    let faded = GrayImage::from_fn(128, 128, |x, y| {
+        Luma([(50 + img.get_pixel(x, y)[0] as u32 * 3 / 4) as u8])
+    });
+    let other = GrayImage::from_fn(128, 128, |x, y| {
+        let (x, y) = (x as f32, y as f32);
+        Luma([(128. + 60. * (y / 4.).cos() + 40. * (x / 9. - y / 13.).sin()) as u8])
+    });
+
+    let hasher = HasherConfig::new().hash_alg(HashAlg::MarrHildreth).to_hasher();
+    let hash = hasher.hash_image(&img);
+
+    assert_eq!(hasher.hash_bits(), 576);
+    assert_eq!(hash.as_bytes().len(), 72);
+    // within 10% of the bits, and near half for an unrelated image
+    assert!(hash.dist(&hasher.hash_image(&faded)) <= 57);
+    assert!(hash.dist(&hasher.hash_image(&other)) >= 200);
+}
+
+#[test]
+#[should_panic(expected = "Marr-Hildreth kernel radius")]
+fn test_mh_kernel_params() {
+    MhKernel::new(2., 6.5);
+}
@@ -1,6 +1,35 @@
+pub(crate) mod alpha;
 mod blockhash;
+mod color_moment;
+mod color_space;
+pub(crate) mod dihedral;
+mod marr_hildreth;
+mod pdq;
+pub(crate) mod pyramid;
+mod radial;
+mod ring;
+pub(crate) mod segment;
+pub(crate) mod sequence;
+mod simhash;
+pub(crate) mod tile;
+pub(crate) mod trim;
 
-use {BitSet, HashCtxt, Image};
+pub use self::color_space::{ColorSpace, Grayscale};
+pub use self::dihedral::DihedralHashes;
+pub use self::pdq::{PdqHash, PdqDihedralHashes};
+pub use self::pyramid::PyramidHash;
+pub use self::radial::{RadialHash, RadialParams};
+pub use self::segment::{MultiHash, SegmentParams};
+pub use self::sequence::{Keyframe, SequenceHash};
+pub use self::tile::{TileHash, TileHashes};
+pub use self::trim::TrimParams;
+
+pub(crate) use self::marr_hildreth::MhKernel;
+pub(crate) use self::simhash::SimHashPlanes;
+
+use image::imageops::{self, FilterType};
+
+use {BitSet, CowImage, HashCtxt, HashVals, Image};
 
 use self::HashAlg::*;
 use HashVals::*;
@@ -17,6 +46,7 @@ use CowImage::*;
 /// ### Choosing an Algorithm
 /// Each algorithm has different performance characteristics
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::manual_non_exhaustive)]
 pub enum HashAlg {
     /// The Mean hashing algorithm.
     ///
@@ -69,10 +99,196 @@ pub enum HashAlg {
     /// Compared to the other algorithms, this does not require any preprocessing steps and so
     /// may be significantly faster at the cost of some resilience.
     ///
+    /// This is the "precise" method (method 2) of the reference implementation, where pixels that
+    /// straddle the boundaries between blocks are weighted between them. The hash bytes formatted
+    /// as hexadecimal match the output of the reference implementation.
+    ///
     /// The algorithm is described in a high level here:
     /// https://github.com/commonsmachinery/blockhash-rfc/blob/master/main.md
+    ///
+    /// ### Breaking Change in 4.0
+    /// Hashes from version 3.x of this crate are not comparable with current ones and should be
+    /// recalculated. Version 3.x packed the bits least-significant first instead of the
+    /// reference's most-significant first, did not scale grayscale pixels to the same range as
+    /// RGB pixels (`3 * luma`), and weighted pixels straddling block boundaries incorrectly.
     Blockhash,
 
+    /// The Median hashing algorithm.
+    ///
+    /// Equivalent to [`Mean`](#variant.Mean) except the pixels of the descaled image are compared
+    /// to the median pixel value instead of the mean.
+    ///
+    /// The mean is easily skewed by a small number of very bright or very dark pixels, which
+    /// can produce hashes that are mostly zeroes or mostly ones. Thresholding on the median
+    /// instead guarantees that roughly half the bits of the hash are set.
+    Median,
+
+    /// The DCT hashing algorithm, better known as pHash.
+    ///
+    /// The image is converted to grayscale, scaled down to `(hash_width * 4) x (hash_height * 4)`
+    /// (32 x 32 for the default hash size) and the Discrete Cosine Transform is performed on the
+    /// luminance values. The `hash_width x hash_height` block of lowest-frequency coefficients,
+    /// excluding the first row and column which contain the DC coefficient, is then compared to
+    /// its median to generate the hash bits.
+    ///
+    /// This is the canonical pHash as implemented by other libraries and differs from
+    /// [`HasherConfig::preproc_dct()`](struct.HasherConfig.html#method.preproc_dct), which keeps
+    /// the DC coefficient and feeds the coefficients to another algorithm. That option has no
+    /// effect with this algorithm.
+    ///
+    /// Further Reading:
+    /// http://www.hackerfactor.com/blog/?/archives/432-Looks-Like-It.html
+    /// https://www.phash.org/
+    Dct,
+
+    /// The Wavelet hashing algorithm, also known as wHash.
+    ///
+    /// The image is converted to grayscale, scaled down to
+    /// `(hash_width * 2^levels) x (hash_height * 2^levels)` and a multi-level 2D Haar Discrete
+    /// Wavelet Transform is performed on the luminance values, leaving a `hash_width x hash_height`
+    /// LL (low-pass) band which is compared to its median to generate the hash bits. By default
+    /// the lowest-frequency band of the full decomposition is removed first.
+    ///
+    /// The number of levels and removal of the lowest-frequency band are set with
+    /// [`HasherConfig::wavelet_params()`](struct.HasherConfig.html#method.wavelet_params).
+    ///
+    /// Further Reading:
+    /// https://github.com/JohannesBuchner/imagehash
+    /// https://en.wikipedia.org/wiki/Haar_wavelet
+    Wavelet,
+
+    /// The [PDQ](https://github.com/facebook/ThreatExchange/tree/main/pdq) algorithm.
+    ///
+    /// The image is converted to luminance and downsampled to 64 x 64 with a Jarosz (box)
+    /// filter, then the 16 x 16 lowest-frequency DCT coefficients, excluding the DC row and
+    /// column, are compared to their median, following the reference implementation.
+    ///
+    /// The hash size is always 16 x 16 (256 bits) and the resize filter is not used.
+    ///
+    /// To also get the image quality metric or the hashes of rotated and flipped versions of
+    /// the image, see [`PdqHash`](struct.PdqHash.html) and
+    /// [`PdqDihedralHashes`](struct.PdqDihedralHashes.html).
+    Pdq,
+
+    /// The Marr-Hildreth hashing algorithm, based on the one from pHash.
+    ///
+    /// The image is converted to grayscale, blurred, scaled to 512 x 512 and its histogram is
+    /// equalized. It is then filtered with a Mexican hat (Laplacian of Gaussian) kernel, which
+    /// responds to edges, and the response is summed over 16 x 16 blocks. The blocks are taken
+    /// in 3 x 3 groups and each block is compared to the mean of its group to generate the
+    /// hash bits.
+    ///
+    /// Because it operates on edges, this is resilient against changes in brightness and
+    /// contrast, and against heavy recompression of line art. It is also much slower than
+    /// the other algorithms.
+    ///
+    /// The hash size is always 24 x 24 (576 bits). The kernel is controlled by the `alpha` and
+    /// `level` parameters set with
+    /// [`HasherConfig::marr_hildreth_params()`](struct.HasherConfig.html#method.marr_hildreth_params).
+    ///
+    /// Further Reading:
+    /// https://www.phash.org/docs/pubs/thesis_zauner.pdf
+    /// https://en.wikipedia.org/wiki/Marr%E2%80%93Hildreth_algorithm
+    MarrHildreth,
+
+    /// The Block Mean Value hashing algorithm, following `BlockMeanHash` from OpenCV's
+    /// `img_hash` module in mode 0.
+    ///
+    /// The image is scaled to 256 x 256 and converted to grayscale with OpenCV's fixed-point
+    /// Rec. 601 weights, then the mean of each 16 x 16 block is taken and compared to the median
+    /// of the block means.
+    ///
+    /// Unlike [`Blockhash`](#variant.Blockhash) the image is resized first and a single global
+    /// median is used. The hash size is always 16 x 16 (256 bits).
+    /// [`FilterType::Triangle`](enum.FilterType.html) is the closest resize filter to OpenCV's,
+    /// but the hashes haven't been checked against OpenCV's output.
+    ///
+    /// Further Reading:
+    /// https://docs.opencv.org/master/df/d55/classcv_1_1img__hash_1_1BlockMeanHash.html
+    BlockMean,
+
+    /// The Block Mean Value hashing algorithm with half-overlapping blocks, following
+    /// `BlockMeanHash` from OpenCV's `img_hash` module in mode 1.
+    ///
+    /// Equivalent to [`BlockMean`](#variant.BlockMean) except the blocks are taken every 8 pixels
+    /// so each overlaps its neighbors by half. The hash size is always 31 x 31 (961 bits).
+    BlockMeanOverlap,
+
+    /// The "quick" method (method 1) of [the Blockhash.io algorithm](#variant.Blockhash).
+    ///
+    /// The image is divided into blocks of whole pixels and any leftover pixels on the right and
+    /// bottom edges are ignored. This is the same as [`Blockhash`](#variant.Blockhash) when the
+    /// image dimensions are multiples of the hash size.
+    ///
+    /// As in the reference implementation, images smaller than the hash size in either dimension
+    /// produce a hash of all zeroes; use [`Blockhash`](#variant.Blockhash) for small images.
+    BlockhashQuick,
+
+    /// The Diagonal-Gradient hashing algorithm.
+    ///
+    /// Equivalent to [`Gradient`](#variant.Gradient) but comparing each pixel with its neighbor
+    /// diagonally down and to the right (top-left to bottom-right). The grayscaled image is
+    /// resized to `(hash_width + 1) x (hash_height + 1)` so that there are `hash_width`
+    /// comparisons per row.
+    ///
+    /// Images with strong diagonal structure (such as architecture or diagrams) can produce
+    /// nearly constant bits with the horizontal and vertical gradients; this may do better.
+    DiagGradient,
+
+    /// The Anti-Diagonal-Gradient hashing algorithm.
+    ///
+    /// Equivalent to [`DiagGradient`](#variant.DiagGradient) but comparing each pixel with its
+    /// neighbor diagonally down and to the left (top-right to bottom-left).
+    AntiDiagGradient,
+
+    /// The Quad-Gradient hashing algorithm.
+    ///
+    /// Combines the comparisons of [`Gradient`](#variant.Gradient),
+    /// [`VertGradient`](#variant.VertGradient), [`DiagGradient`](#variant.DiagGradient) and
+    /// [`AntiDiagGradient`](#variant.AntiDiagGradient) in that order; resizes the grayscaled image
+    /// to `(width / 2 + 1) x (height / 2 + 1)` and compares each of the `(width / 2) x (height / 2)`
+    /// top-left pixels in all four directions.
+    QuadGradient,
+
+    /// The Ring Partition hashing algorithm, which is invariant to rotating the image by any angle.
+    ///
+    /// The image is converted to grayscale and scaled down to a square, and the circle inscribed
+    /// in the square is divided into `hash_width * hash_height + 1` concentric rings of equal
+    /// area. The mean luminance of each ring is compared to that of the next ring out to generate
+    /// the hash bits, like [`Gradient`](#variant.Gradient) does for the pixels of each row.
+    ///
+    /// Rotating the image around its center mostly moves pixels within their rings, so the
+    /// hash is nearly unchanged. The corners of the image are ignored, and because the image
+    /// is resized to a square first, rotations of non-square images are not handled as well.
+    /// The side of the square is `8 * ceil(sqrt(hash_width * hash_height + 1))`
+    /// (72 x 72 for the default hash size).
+    ///
+    /// Further Reading:
+    /// https://doi.org/10.1109/TIFS.2015.2485163
+    RingPartition,
+
+    /// The SimHash (random hyperplane) locality-sensitive hashing algorithm.
+    ///
+    /// The image is converted to grayscale and scaled down to `hash_width x hash_height` (or the
+    /// DCT coefficients are calculated, if
+    /// [`preproc_dct()`](struct.HasherConfig.html#method.preproc_dct) is set), the values are
+    /// centered on their mean and then projected onto random hyperplanes
+    /// with normally distributed coefficients. Each bit of the hash is set if the values are
+    /// on the positive side of a hyperplane.
+    ///
+    /// The fraction of bits that differ between two hashes estimates the angle between the
+    /// centered values of the images, so the Hamming distance preserves cosine similarity, which
+    /// is useful for approximate nearest neighbor indexes.
+    ///
+    /// Unlike the other algorithms, the number of bits is not tied to the hash size: it's set
+    /// along with the seed of the hyperplanes with
+    /// [`HasherConfig::simhash_params()`](struct.HasherConfig.html#method.simhash_params).
+    /// Hashes are only comparable if they were generated with the same parameters.
+    ///
+    /// Further Reading:
+    /// https://en.wikipedia.org/wiki/SimHash
+    SimHash,
+
     /// EXHAUSTIVE MATCHING IS NOT RECOMMENDED FOR BACKWARDS COMPATIBILITY REASONS
     /// New variants may be added in minor (x.[y + 1].z) releases
     #[doc(hidden)]
@@ -80,14 +296,29 @@ pub enum HashAlg {
     __Nonexhaustive,
 }
 
-fn next_multiple_of_2(x: u32) -> u32 { x + 1 & !1 }
-fn next_multiple_of_4(x: u32) -> u32 { x + 3 & !3 }
+/// The image is resized to this size for the Block Mean Value algorithms.
+const BLOCK_MEAN_RESIZE: u32 = 256;
+/// The size of the blocks for the Block Mean Value algorithms.
+const BLOCK_MEAN_BLOCK: u32 = 16;
+
+fn next_multiple_of_2(x: u32) -> u32 { (x + 1) & !1 }
+fn next_multiple_of_4(x: u32) -> u32 { (x + 3) & !3 }
 
 impl HashAlg {
     pub (crate) fn hash_image<I, B>(&self, ctxt: &HashCtxt, image: &I) -> B
     where I: Image, B: BitSet {
         let post_gauss = ctxt.gauss_preproc(image);
 
+        if ctxt.has_gray_preproc() {
+            let grayscale = ctxt.gray_preproc(post_gauss.to_grayscale(&ctxt.grayscale));
+            return self.hash_post_preproc(ctxt, Borrowed(&*grayscale));
+        }
+
+        self.hash_post_preproc(ctxt, post_gauss)
+    }
+
+    fn hash_post_preproc<I, B>(&self, ctxt: &HashCtxt, post_gauss: CowImage<I>) -> B
+    where I: Image, B: BitSet {
         let HashCtxt { width, height, .. } = *ctxt;
 
         if *self == Blockhash {
@@ -97,16 +328,93 @@ impl HashAlg {
             };
         }
 
-        let grayscale = post_gauss.to_grayscale();
+        if *self == BlockhashQuick {
+            return match post_gauss {
+                Borrowed(img) => blockhash::blockhash_quick(img, width, height),
+                Owned(img) => blockhash::blockhash_quick(&img, width, height),
+            };
+        }
+
+        if *self == Pdq {
+            let bytes = match post_gauss {
+                Borrowed(img) => pdq::pdq_hash_bytes(img),
+                Owned(ref img) => pdq::pdq_hash_bytes(img),
+            };
+
+            return B::from_iter(bytes.iter().cloned());
+        }
+
+        if *self == BlockMean || *self == BlockMeanOverlap {
+            let luma = match post_gauss {
+                Borrowed(img) => block_mean_luma(img, ctxt.resize_filter),
+                Owned(ref img) => block_mean_luma(img, ctxt.resize_filter),
+            };
+
+            return self.hash_vals(Bytes(luma), BLOCK_MEAN_RESIZE as usize);
+        }
+
+        let grayscale = post_gauss.to_grayscale(&ctxt.grayscale);
+
+        if *self == Dct {
+            let dct_ctxt = ctxt.dct_ctxt.as_ref().expect("DCT context not initialized");
+            let coeffs = ctxt.dct_coeffs(dct_ctxt, &grayscale);
+            return B::from_bools(strict_median_hash(&dct_ctxt.crop_low_freq(&coeffs, width,
+                                                                            height)));
+        }
+
+        if *self == MarrHildreth {
+            let kernel = ctxt.mh_kernel.as_ref().expect("Marr-Hildreth kernel not initialized");
+            let bits = marr_hildreth::mh_hash(&grayscale, kernel, ctxt.resize_filter);
+            return B::from_bools(bits.into_iter());
+        }
+
+        if *self == RingPartition {
+            let (side, _) = self.resize_dimensions(width, height);
+            let resized = imageops::resize(&*grayscale, side, side, ctxt.resize_filter);
+            let bits = ring::ring_hash(&resized, side as usize, (width * height) as usize);
+            return B::from_bools(bits.into_iter());
+        }
+
+        if *self == SimHash {
+            let planes = ctxt.simhash_planes.as_ref().expect("SimHash planes not initialized");
+            let (resize_width, resize_height) = self.resize_dimensions(width, height);
+
+            let vals = match ctxt.calc_hash_vals(&grayscale, resize_width, resize_height) {
+                Floats(floats) => floats,
+                Bytes(bytes) => bytes.into_iter().map(|x| x as f32).collect(),
+            };
+
+            return B::from_bools(planes.hash(&vals));
+        }
+
+        if *self == Wavelet {
+            let dwt_ctxt = ctxt.dwt_ctxt.as_ref().expect("DWT context not initialized");
+            return B::from_bools(strict_median_hash(&ctxt.dwt_ll_band(dwt_ctxt, &grayscale)));
+        }
+
         let (resize_width, resize_height) = self.resize_dimensions(width, height);
 
-        let hash_vals = ctxt.calc_hash_vals(&*grayscale, resize_width, resize_height);
+        let hash_vals = ctxt.calc_hash_vals(&grayscale, resize_width, resize_height);
 
-        let rowstride = resize_width as usize;
+        self.hash_vals(hash_vals, resize_width as usize)
+    }
 
+    /// Calculate the hash from the values of the resized image (or its DCT coefficients),
+    /// for the algorithms where [`hashes_resized`](#method.hashes_resized) is `true`.
+    pub (crate) fn hash_vals<B: BitSet>(&self, hash_vals: HashVals, rowstride: usize) -> B {
         match (*self, hash_vals) {
             (Mean, Floats(ref floats)) => B::from_bools(mean_hash_f32(floats)),
             (Mean, Bytes(ref bytes)) => B::from_bools(mean_hash_u8(bytes)),
+            (BlockMean, Bytes(ref bytes)) => {
+                let sums = block_sums(bytes, rowstride, BLOCK_MEAN_BLOCK);
+                B::from_bools(median_hash(&sums))
+            },
+            (BlockMeanOverlap, Bytes(ref bytes)) => {
+                let sums = block_sums(bytes, rowstride, BLOCK_MEAN_BLOCK / 2);
+                B::from_bools(median_hash(&sums))
+            },
+            (Median, Floats(ref floats)) => B::from_bools(median_hash(floats)),
+            (Median, Bytes(ref bytes)) => B::from_bools(median_hash(bytes)),
             (Gradient, Floats(ref floats)) => B::from_bools(gradient_hash(floats, rowstride)),
             (Gradient, Bytes(ref bytes)) => B::from_bools(gradient_hash(bytes, rowstride)),
             (VertGradient, Floats(ref floats)) => B::from_bools(vert_gradient_hash(floats,
@@ -116,25 +424,68 @@ impl HashAlg {
                                                                                        rowstride)),
             (DoubleGradient, Bytes(ref bytes)) => B::from_bools(double_gradient_hash(bytes,
                                                                                      rowstride)),
-            (Blockhash, _) | (__Nonexhaustive, _) => unreachable!(),
+            (DiagGradient, Floats(ref floats)) => B::from_bools(diag_gradient_hash(floats,
+                                                                                   rowstride)),
+            (DiagGradient, Bytes(ref bytes)) => B::from_bools(diag_gradient_hash(bytes, rowstride)),
+            (AntiDiagGradient, Floats(ref floats)) => B::from_bools(anti_diag_gradient_hash(floats,
+                                                                                            rowstride)),
+            (AntiDiagGradient, Bytes(ref bytes)) => B::from_bools(anti_diag_gradient_hash(bytes,
+                                                                                          rowstride)),
+            (QuadGradient, Floats(ref floats)) => B::from_bools(quad_gradient_hash(floats,
+                                                                                   rowstride)),
+            (QuadGradient, Bytes(ref bytes)) => B::from_bools(quad_gradient_hash(bytes, rowstride)),
+            (BlockMean, Floats(_)) | (BlockMeanOverlap, Floats(_)) => unreachable!(),
+            (Dct, _) | (Wavelet, _) | (MarrHildreth, _) | (Blockhash, _) | (BlockhashQuick, _)
+            | (Pdq, _) | (RingPartition, _) | (SimHash, _)
+            | (__Nonexhaustive, _) => unreachable!(),
         }
     }
 
     pub (crate) fn round_hash_size(&self, width: u32, height: u32) -> (u32, u32) {
         match *self {
-            DoubleGradient => (next_multiple_of_2(width), next_multiple_of_2(height)),
-            Blockhash => (next_multiple_of_4(width), next_multiple_of_4(height)),
+            DoubleGradient | QuadGradient => (next_multiple_of_2(width), next_multiple_of_2(height)),
+            Blockhash | BlockhashQuick => (next_multiple_of_4(width), next_multiple_of_4(height)),
+            BlockMean => (BLOCK_MEAN_RESIZE / BLOCK_MEAN_BLOCK, BLOCK_MEAN_RESIZE / BLOCK_MEAN_BLOCK),
+            BlockMeanOverlap => {
+                let side = BLOCK_MEAN_RESIZE / (BLOCK_MEAN_BLOCK / 2) - 1;
+                (side, side)
+            },
+            MarrHildreth => (marr_hildreth::HASH_SIDE, marr_hildreth::HASH_SIDE),
+            Pdq => (16, 16),
             _ => (width, height),
         }
     }
 
+    /// Whether the hash is calculated only from the grayscale image resized to
+    /// `resize_dimensions()`, without any other preprocessing by the algorithm.
+    pub (crate) fn hashes_resized(&self) -> bool {
+        matches!(*self, Mean | Median | Gradient | VertGradient | DoubleGradient | DiagGradient
+                        | AntiDiagGradient | QuadGradient)
+    }
+
+    /// Whether `HasherConfig::preproc_dct()` applies to this algorithm.
+    pub (crate) fn supports_preproc_dct(&self) -> bool {
+        !matches!(*self, Dct | Wavelet | BlockMean | BlockMeanOverlap | MarrHildreth | Blockhash
+                         | BlockhashQuick | Pdq | RingPartition | __Nonexhaustive)
+    }
+
     pub (crate) fn resize_dimensions(&self, width: u32, height: u32) -> (u32, u32) {
         match *self {
-            Mean => (width, height),
-            Blockhash => panic!("Blockhash algorithm does not resize"),
+            Mean | Median | SimHash => (width, height),
+            Dct => (width * 4, height * 4),
+            BlockMean | BlockMeanOverlap => (BLOCK_MEAN_RESIZE, BLOCK_MEAN_RESIZE),
+            Wavelet => panic!("Wavelet algorithm resizes according to its DWT context"),
+            Blockhash | BlockhashQuick => panic!("Blockhash algorithm does not resize"),
+            MarrHildreth => panic!("Marr-Hildreth algorithm always resizes to 512 x 512"),
+            Pdq => panic!("PDQ algorithm performs its own downsampling"),
+            RingPartition => {
+                let side = ring::resize_side(width * height);
+                (side, side)
+            },
             Gradient => (width + 1, height),
             VertGradient => (width, height + 1),
-            DoubleGradient => (width / 2 + 1, height / 2 + 1),
+            DoubleGradient | QuadGradient => (width / 2 + 1, height / 2 + 1),
+            DiagGradient | AntiDiagGradient => (width + 1, height + 1),
             __Nonexhaustive => panic!("not a real hash algorithm"),
         }
     }
@@ -150,6 +501,50 @@ fn mean_hash_f32<'a>(luma: &'a [f32]) -> impl Iterator<Item = bool> + 'a {
     luma.iter().map(move |&x| x >= mean)
 }
 
+fn median_hash<'a, T: PartialOrd + Copy>(luma: &'a [T]) -> impl Iterator<Item = bool> + 'a {
+    let median = blockhash::get_median(luma);
+    luma.iter().map(move |&x| x >= median)
+}
+
+/// Sum the values in each `BLOCK_MEAN_BLOCK` square block, starting a new block every `step`.
+///
+/// The blocks are all the same size so their sums are proportional to their means.
+/// Resize the image for the Block Mean Value algorithms and then convert it to grayscale,
+/// in that order like OpenCV, with the fixed-point Rec. 601 weights of its `cvtColor()`.
+fn block_mean_luma<I: Image>(img: &I, filter: FilterType) -> Vec<u8> {
+    let resized = imageops::resize(&dihedral::to_rgba(img), BLOCK_MEAN_RESIZE, BLOCK_MEAN_RESIZE,
+                                   filter);
+
+    resized.pixels().map(|px| {
+        let [r, g, b] = [px[0] as u32, px[1] as u32, px[2] as u32];
+        ((r * 4899 + g * 9617 + b * 1868 + 8192) >> 14) as u8
+    }).collect()
+}
+
+fn block_sums(luma: &[u8], rowstride: usize, step: u32) -> Vec<u32> {
+    let (block, step) = (BLOCK_MEAN_BLOCK as usize, step as usize);
+    let height = luma.len() / rowstride;
+
+    let mut sums = Vec::new();
+
+    for top in (0 ..= height - block).step_by(step) {
+        for left in (0 ..= rowstride - block).step_by(step) {
+            sums.push(luma[top * rowstride ..].chunks(rowstride).take(block)
+                .flat_map(|row| &row[left .. left + block])
+                .map(|&x| x as u32)
+                .sum());
+        }
+    }
+
+    sums
+}
+
+/// Compare to the median, averaging the middle two values for an even length like pHash does.
+fn strict_median_hash<'a>(coeffs: &'a [f32]) -> impl Iterator<Item = bool> + 'a {
+    let median = blockhash::get_median_avg(coeffs);
+    coeffs.iter().map(move |&x| x as f64 > median)
+}
+
 /// The guts of the gradient hash separated so we can reuse them
 fn gradient_hash_impl<I>(luma: I) -> impl Iterator<Item = bool>
     where I: IntoIterator + Clone, <I as IntoIterator>::Item: PartialOrd {
@@ -168,3 +563,78 @@ fn vert_gradient_hash<'a, T: PartialOrd>(luma: &'a [T], rowstride: usize) -> imp
 fn double_gradient_hash<'a, T: PartialOrd>(luma: &'a [T], rowstride: usize) -> impl Iterator<Item = bool> + 'a {
     gradient_hash(luma, rowstride).chain(vert_gradient_hash(luma, rowstride))
 }
+
+/// Compare each pixel with its neighbor diagonally down and to the right, skipping the last
+/// row and column.
+fn diag_gradient_hash<'a, T: PartialOrd>(luma: &'a [T], rowstride: usize) -> impl Iterator<Item = bool> + 'a {
+    luma.chunks(rowstride).zip(luma.chunks(rowstride).skip(1))
+        .flat_map(|(row, next)| row.iter().zip(&next[1..]).map(|(this, next)| this < next))
+}
+
+/// Compare each pixel with its neighbor diagonally down and to the left, skipping the last
+/// row and the first column.
+fn anti_diag_gradient_hash<'a, T: PartialOrd>(luma: &'a [T], rowstride: usize) -> impl Iterator<Item = bool> + 'a {
+    luma.chunks(rowstride).zip(luma.chunks(rowstride).skip(1))
+        .flat_map(|(row, next)| row[1..].iter().zip(next).map(|(this, next)| this < next))
+}
+
+/// Compare the pixels of the top-left `(rowstride - 1) x (height - 1)` block in all four
+/// directions so each direction produces the same number of bits.
+fn quad_gradient_hash<'a, T: PartialOrd>(luma: &'a [T], rowstride: usize) -> impl Iterator<Item = bool> + 'a {
+    let height = luma.len() / rowstride;
+    let inner = &luma[.. (height - 1) * rowstride];
+
+    inner.chunks(rowstride).flat_map(gradient_hash_impl)
+        .chain((0 .. rowstride - 1).map(move |col_start| luma[col_start..].iter().step_by(rowstride))
+            .flat_map(gradient_hash_impl))
+        .chain(diag_gradient_hash(luma, rowstride))
+        .chain(anti_diag_gradient_hash(luma, rowstride))
+}
+
+#[test]
+fn test_median_hash() {
+    // a single outlier drags the mean above most of the other values
+    let luma = [10u8, 20, 30, 40, 50, 60, 70, 255];
+    assert_eq!(mean_hash_u8(&luma).filter(|&b| b).count(), 2);
+    assert_eq!(median_hash(&luma).filter(|&b| b).count(), 4);
+
+    let floats: Vec<f32> = luma.iter().map(|&x| x as f32).collect();
+    assert_eq!(median_hash(&floats).collect::<Vec<_>>(), median_hash(&luma).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_block_sums() {
+    let luma: Vec<u8> = (0 .. 32 * 32).map(|i| (i % 32 / 16 + i / 32 / 16 * 2) as u8).collect();
+    assert_eq!(block_sums(&luma, 32, 16), [0, 256, 512, 768]);
+    assert_eq!(block_sums(&luma, 32, 8).len(), 9);
+}
+
+#[test]
+fn test_block_mean_luma() {
+    use image::{GrayImage, Luma, Rgb, RgbImage};
+
+    // OpenCV's weights: 255 * 4899 / 16384 rounds down to 76
+    let red = RgbImage::from_pixel(300, 200, Rgb([255, 0, 0]));
+    let luma = block_mean_luma(&red, FilterType::Triangle);
+    assert_eq!(luma.len(), (BLOCK_MEAN_RESIZE * BLOCK_MEAN_RESIZE) as usize);
+    assert!(luma.iter().all(|&x| x == 76));
+
+    // the weights sum to 1 so grayscale images are only resized
+    let gray = GrayImage::from_fn(256, 256, |x, y| Luma([(x ^ y) as u8]));
+    assert_eq!(block_mean_luma(&gray, FilterType::Nearest), gray.into_vec());
+}
+
+#[test]
+fn test_diag_gradient_hash() {
+    // a ramp along the main diagonal with a constant anti-diagonal
+    let luma: Vec<u8> = (0 .. 9).map(|i| (i % 3 + i / 3) as u8 * 10).collect();
+
+    assert!(diag_gradient_hash(&luma, 3).all(|b| b));
+    assert_eq!(diag_gradient_hash(&luma, 3).count(), 4);
+    assert!(anti_diag_gradient_hash(&luma, 3).all(|b| !b));
+    assert_eq!(anti_diag_gradient_hash(&luma, 3).count(), 4);
+
+    let quad: Vec<bool> = quad_gradient_hash(&luma, 3).collect();
+    assert_eq!(quad, [true, true, true, true, true, true, true, true,
+                      true, true, true, true, false, false, false, false]);
+}
@@ -0,0 +1,348 @@
+// Implementation adapted from the reference C++ version:
+// https://github.com/facebook/ThreatExchange/tree/main/pdq/cpp
+//
+// The floating-point operations are performed in the same order as the reference to keep the
+// output as close to it as possible; this has not yet been checked against the reference test
+// vectors, so don't rely on hashes matching those from other implementations bit for bit.
+use {Image, ImageHash};
+
+use super::blockhash::qselect_inplace;
+use super::dihedral::px_to_rgba;
+
+const LUMA_FROM_R: f32 = 0.299;
+const LUMA_FROM_G: f32 = 0.587;
+const LUMA_FROM_B: f32 = 0.114;
+
+const DOWNSAMPLE_DIMS: usize = 64;
+const DCT_DIMS: usize = 16;
+const NUM_JAROSZ_PASSES: usize = 2;
+
+type Buffer16x16 = [[f32; DCT_DIMS]; DCT_DIMS];
+
+/// A PDQ hash along with the quality metric of the image it was calculated from.
+///
+/// Get an instance with [`PdqHash::from_image()`](#method.from_image).
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct PdqHash {
+    /// The 256-bit hash.
+    ///
+    /// The hash bytes formatted as hexadecimal follow the layout of the reference implementation's
+    /// output.
+    pub hash: ImageHash<[u8; 32]>,
+    /// The quality of the image, in `[0, 100]`.
+    ///
+    /// This measures the amount of gradient in the image; featureless images such as solid
+    /// colors produce low values and their hashes are not very meaningful for matching.
+    pub quality: u32,
+}
+
+impl PdqHash {
+    /// Calculate the PDQ hash of the given image.
+    pub fn from_image<I: Image>(img: &I) -> PdqHash {
+        let (dct, quality) = pdq_dct(img);
+
+        PdqHash { hash: dct_to_hash(&dct), quality }
+    }
+}
+
+/// The PDQ hashes of the eight rotations and reflections of an image.
+///
+/// These are calculated from a single DCT so this is much cheaper than transforming the
+/// image and hashing it eight times. The names follow those used by the reference implementation.
+///
+/// Get an instance with [`PdqDihedralHashes::from_image()`](#method.from_image).
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct PdqDihedralHashes {
+    /// The hash of the image as-is.
+    pub original: ImageHash<[u8; 32]>,
+    /// The hash of the image rotated 90 degrees counterclockwise.
+    pub rotate90: ImageHash<[u8; 32]>,
+    /// The hash of the image rotated 180 degrees.
+    pub rotate180: ImageHash<[u8; 32]>,
+    /// The hash of the image rotated 270 degrees counterclockwise.
+    pub rotate270: ImageHash<[u8; 32]>,
+    /// The hash of the image flipped about the X axis (upside-down).
+    pub flip_x: ImageHash<[u8; 32]>,
+    /// The hash of the image flipped about the Y axis (mirrored left-to-right).
+    pub flip_y: ImageHash<[u8; 32]>,
+    /// The hash of the image flipped about the main diagonal (transposed).
+    pub flip_plus1: ImageHash<[u8; 32]>,
+    /// The hash of the image flipped about the anti-diagonal.
+    pub flip_minus1: ImageHash<[u8; 32]>,
+    /// The quality of the image, in `[0, 100]`. See [`PdqHash::quality`](struct.PdqHash.html#structfield.quality).
+    pub quality: u32,
+}
+
+impl PdqDihedralHashes {
+    /// Calculate the PDQ hashes of all rotations and reflections of the given image.
+    pub fn from_image<I: Image>(img: &I) -> PdqDihedralHashes {
+        let (dct, quality) = pdq_dct(img);
+
+        // each transform negates the odd-frequency coefficients along the flipped axes
+        // and/or transposes the coefficients; the stored indices are offset by one from
+        // the frequencies as the DC row and column are skipped
+        let transformed = |transpose: bool, negate: fn(usize, usize) -> bool| {
+            let mut out = [[0f32; DCT_DIMS]; DCT_DIMS];
+
+            for (i, row) in dct.iter().enumerate() {
+                for (j, &val) in row.iter().enumerate() {
+                    let val = if negate(i, j) { -val } else { val };
+
+                    if transpose {
+                        out[j][i] = val;
+                    } else {
+                        out[i][j] = val;
+                    }
+                }
+            }
+
+            dct_to_hash(&out)
+        };
+
+        PdqDihedralHashes {
+            original: dct_to_hash(&dct),
+            rotate90: transformed(true, |_, j| j & 1 == 0),
+            rotate180: transformed(false, |i, j| (i + j) & 1 == 1),
+            rotate270: transformed(true, |i, _| i & 1 == 0),
+            flip_x: transformed(false, |i, _| i & 1 == 0),
+            flip_y: transformed(false, |_, j| j & 1 == 0),
+            flip_plus1: transformed(true, |_, _| false),
+            flip_minus1: transformed(true, |i, j| (i + j) & 1 == 1),
+            quality,
+        }
+    }
+
+    /// Iterate over all eight hashes, starting with the original.
+    pub fn iter(&self) -> impl Iterator<Item = &ImageHash<[u8; 32]>> {
+        vec![
+            &self.original, &self.rotate90, &self.rotate180, &self.rotate270,
+            &self.flip_x, &self.flip_y, &self.flip_plus1, &self.flip_minus1,
+        ].into_iter()
+    }
+}
+
+/// Get the bytes of the PDQ hash of `img`; used by `HashAlg::Pdq`.
+pub fn pdq_hash_bytes<I: Image>(img: &I) -> [u8; 32] {
+    let (dct, _) = pdq_dct(img);
+    dct_bits(&dct)
+}
+
+/// Downsample the image and calculate the 16 x 16 DCT coefficients and quality metric.
+fn pdq_dct<I: Image>(img: &I) -> (Buffer16x16, u32) {
+    let (width, height) = img.dimensions();
+    let (num_cols, num_rows) = (width as usize, height as usize);
+
+    let mut luma = vec![0f32; num_cols * num_rows];
+
+    img.foreach_pixel8(|x, y, px| {
+        let [r, g, b, _] = px_to_rgba(px);
+
+        luma[y as usize * num_cols + x as usize] =
+            LUMA_FROM_R * r as f32 + LUMA_FROM_G * g as f32 + LUMA_FROM_B * b as f32;
+    });
+
+    let mut scratch = vec![0f32; luma.len()];
+
+    let window_along_rows = jarosz_window_size(num_cols);
+    let window_along_cols = jarosz_window_size(num_rows);
+
+    for _ in 0 .. NUM_JAROSZ_PASSES {
+        box_along_rows(&luma, &mut scratch, num_rows, num_cols, window_along_rows);
+        box_along_cols(&scratch, &mut luma, num_rows, num_cols, window_along_cols);
+    }
+
+    let downsampled = decimate(&luma, num_rows, num_cols);
+
+    (dct_64_to_16(&downsampled), quality_metric(&downsampled))
+}
+
+fn jarosz_window_size(old_dimension: usize) -> usize {
+    (old_dimension + 2 * DOWNSAMPLE_DIMS - 1) / (2 * DOWNSAMPLE_DIMS)
+}
+
+fn box_along_rows(input: &[f32], output: &mut [f32], num_rows: usize, num_cols: usize,
+                  window_size: usize) {
+    for i in 0 .. num_rows {
+        let start = i * num_cols;
+        box_1d(&input[start..], &mut output[start..], num_cols, 1, window_size);
+    }
+}
+
+fn box_along_cols(input: &[f32], output: &mut [f32], num_rows: usize, num_cols: usize,
+                  window_size: usize) {
+    for j in 0 .. num_cols {
+        box_1d(&input[j..], &mut output[j..], num_rows, num_cols, window_size);
+    }
+}
+
+/// Box filter along a strided vector, shrinking the window at the edges.
+fn box_1d(input: &[f32], output: &mut [f32], len: usize, stride: usize, window_size: usize) {
+    let half_window_size = (window_size + 2) / 2;
+
+    let mut left = 0;
+    let mut right = 0;
+    let mut out = 0;
+    let mut sum = 0f32;
+    let mut cur_window_size = 0;
+
+    // accumulate the first sum without writing
+    for _ in 0 .. half_window_size - 1 {
+        sum += input[right];
+        cur_window_size += 1;
+        right += stride;
+    }
+
+    // initial writes with a growing window
+    for _ in 0 .. window_size - half_window_size + 1 {
+        sum += input[right];
+        cur_window_size += 1;
+        output[out] = sum / cur_window_size as f32;
+        right += stride;
+        out += stride;
+    }
+
+    // writes with the full window
+    for _ in 0 .. len - window_size {
+        sum += input[right];
+        sum -= input[left];
+        output[out] = sum / cur_window_size as f32;
+        left += stride;
+        right += stride;
+        out += stride;
+    }
+
+    // final writes with a shrinking window
+    for _ in 0 .. half_window_size - 1 {
+        sum -= input[left];
+        cur_window_size -= 1;
+        output[out] = sum / cur_window_size as f32;
+        left += stride;
+        out += stride;
+    }
+}
+
+fn decimate(input: &[f32], num_rows: usize, num_cols: usize) -> Vec<f32> {
+    let mut out = Vec::with_capacity(DOWNSAMPLE_DIMS * DOWNSAMPLE_DIMS);
+
+    for i in 0 .. DOWNSAMPLE_DIMS {
+        let ini = ((i as f64 + 0.5) * num_rows as f64 / DOWNSAMPLE_DIMS as f64) as usize;
+
+        for j in 0 .. DOWNSAMPLE_DIMS {
+            let inj = ((j as f64 + 0.5) * num_cols as f64 / DOWNSAMPLE_DIMS as f64) as usize;
+            out.push(input[ini * num_cols + inj]);
+        }
+    }
+
+    out
+}
+
+fn quality_metric(buf: &[f32]) -> u32 {
+    let diff = |u: f32, v: f32| (((u - v) * 100.) / 255.) as i32;
+
+    let vert: i32 = buf.chunks(DOWNSAMPLE_DIMS).zip(buf.chunks(DOWNSAMPLE_DIMS).skip(1))
+        .flat_map(|(upper, lower)| upper.iter().zip(lower).map(|(&u, &v)| diff(u, v).abs()))
+        .sum();
+
+    let horiz: i32 = buf.chunks(DOWNSAMPLE_DIMS)
+        .flat_map(|row| row.iter().zip(&row[1..]).map(|(&u, &v)| diff(u, v).abs()))
+        .sum();
+
+    // heuristic scaling factor from the reference
+    ((vert + horiz) / 90).min(100) as u32
+}
+
+/// Calculate the 16 x 16 lowest-frequency DCT coefficients of the 64 x 64 buffer,
+/// skipping the DC row and column.
+fn dct_64_to_16(buf: &[f32]) -> Buffer16x16 {
+    let scale = (2.0f64 / DOWNSAMPLE_DIMS as f64).sqrt();
+
+    let mut dct_matrix = [[0f32; DOWNSAMPLE_DIMS]; DCT_DIMS];
+
+    for (i, row) in dct_matrix.iter_mut().enumerate() {
+        for (j, val) in row.iter_mut().enumerate() {
+            *val = (scale * ((::std::f64::consts::PI / 2. / DOWNSAMPLE_DIMS as f64)
+                * (i + 1) as f64 * (2 * j + 1) as f64).cos()) as f32;
+        }
+    }
+
+    // T = D * A
+    let mut temp = [[0f32; DOWNSAMPLE_DIMS]; DCT_DIMS];
+
+    for (d_row, t_row) in dct_matrix.iter().zip(temp.iter_mut()) {
+        for (j, t) in t_row.iter_mut().enumerate() {
+            *t = d_row.iter().enumerate()
+                .fold(0., |sum, (k, &d)| sum + d * buf[k * DOWNSAMPLE_DIMS + j]);
+        }
+    }
+
+    // B = T * D^T
+    let mut out = [[0f32; DCT_DIMS]; DCT_DIMS];
+
+    for (t_row, out_row) in temp.iter().zip(out.iter_mut()) {
+        for (d_row, out) in dct_matrix.iter().zip(out_row.iter_mut()) {
+            *out = t_row.iter().zip(d_row.iter()).fold(0., |sum, (&t, &d)| sum + t * d);
+        }
+    }
+
+    out
+}
+
+fn dct_to_hash(dct: &Buffer16x16) -> ImageHash<[u8; 32]> {
+    ImageHash { hash: dct_bits(dct), __backcompat: () }
+}
+
+/// Threshold the coefficients against their median and pack the bits like the reference.
+fn dct_bits(dct: &Buffer16x16) -> [u8; 32] {
+    let mut scratch: Vec<f32> = dct.iter().flat_map(|row| row.iter().cloned()).collect();
+    // the reference takes the lower median
+    let median_idx = (scratch.len() - 1) / 2;
+    let median = *qselect_inplace(&mut scratch, median_idx);
+
+    let mut bytes = [0u8; 32];
+
+    // the reference stores row `i` in a 16-bit word `i` and prints the words from last to first,
+    // most significant byte first
+    for (i, row) in dct.iter().enumerate() {
+        for (j, &val) in row.iter().enumerate() {
+            if val > median {
+                let byte = (DCT_DIMS - 1 - i) * 2 + if j < 8 { 1 } else { 0 };
+                bytes[byte] |= 1 << (j & 7);
+            }
+        }
+    }
+
+    bytes
+}
+
+#[test]
+fn test_box_1d() {
+    let input = [1f32, 2., 3., 4., 5.];
+    let mut output = [0f32; 5];
+
+    box_1d(&input, &mut output, 5, 1, 3);
+    assert_eq!(output, [1.5, 2., 3., 4., 4.5]);
+
+    // a window size of 1 is a no-op
+    box_1d(&input, &mut output, 5, 1, 1);
+    assert_eq!(output, input);
+}
+
+#[test]
+fn test_dihedral_hashes() {
+    use image::{imageops, GrayImage, Luma};
+
+    // at 64 x 64 the downsampling is a no-op so the transforms should be exact
+    let img = GrayImage::from_fn(64, 64, |x, y| Luma([((x * 7 + y * 13) ^ (x * y)) as u8]));
+    let hashes = PdqDihedralHashes::from_image(&img);
+
+    let rotated = |img| PdqHash::from_image(&img).hash;
+
+    assert_eq!(hashes.rotate90, rotated(imageops::rotate270(&img)));
+    assert_eq!(hashes.rotate180, rotated(imageops::rotate180(&img)));
+    assert_eq!(hashes.rotate270, rotated(imageops::rotate90(&img)));
+    assert_eq!(hashes.flip_x, rotated(imageops::flip_vertical(&img)));
+    assert_eq!(hashes.flip_y, rotated(imageops::flip_horizontal(&img)));
+    // mirrored across the main diagonal and the anti-diagonal
+    assert_eq!(hashes.flip_plus1, rotated(imageops::flip_horizontal(&imageops::rotate90(&img))));
+    assert_eq!(hashes.flip_minus1, rotated(imageops::flip_vertical(&imageops::rotate90(&img))));
+}
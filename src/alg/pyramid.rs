@@ -0,0 +1,86 @@
+use {HashBytes, ImageHash};
+
+/// The side of each cell of the finest level of the pyramid, in pixels of the intermediate image.
+pub const CELL_SIDE: u32 = 64;
+
+/// The most levels a pyramid may have; the intermediate image is then 2048 x 2048 pixels and
+/// the finest level has 1024 cells.
+pub const MAX_LEVELS: u32 = 6;
+
+/// The hashes of an image at several scales: the whole image, then 2 x 2 quadrants,
+/// then 4 x 4 cells, and so on.
+///
+/// Get an instance with
+/// [`Hasher::hash_image_pyramid()`](struct.Hasher.html#method.hash_image_pyramid).
+///
+/// Comparing the coarse levels first is a cheap way to filter out dissimilar images, and the
+/// distances of the cells of the finer levels show where two images differ.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct PyramidHash<B = Box<[u8]>> {
+    levels: Vec<Vec<ImageHash<B>>>,
+}
+
+impl<B: HashBytes> PyramidHash<B> {
+    /// Create a `PyramidHash` from the hashes of each level, e.g. to restore stored hashes.
+    ///
+    /// Level `n` should have `4^n` hashes in row-major order.
+    pub fn from_levels(levels: Vec<Vec<ImageHash<B>>>) -> Self {
+        PyramidHash { levels }
+    }
+
+    /// Get the hashes of each level, from coarsest to finest.
+    ///
+    /// Level `n` has `2^n x 2^n` hashes in row-major order.
+    pub fn levels(&self) -> &[Vec<ImageHash<B>>] { &self.levels }
+
+    /// Calculate the mean Hamming distance between the cells of each level of this and `other`.
+    pub fn level_dists(&self, other: &Self) -> Vec<f32> {
+        self.levels.iter().zip(&other.levels).map(|(level, other)| {
+            let total: u32 = level.iter().zip(other).map(|(l, r)| l.dist(r)).sum();
+            total as f32 / level.len().max(1) as f32
+        }).collect()
+    }
+
+    /// Calculate the weighted mean of the [level distances](#method.level_dists) with
+    /// `weights[n]` as the weight of level `n`.
+    ///
+    /// Levels without a weight are ignored, so a single weight compares the whole images only.
+    pub fn dist(&self, other: &Self, weights: &[f32]) -> f32 {
+        let (total, weight_sum) = self.level_dists(other).iter().zip(weights)
+            .fold((0., 0.), |(total, sum), (dist, weight)| (total + dist * weight, sum + weight));
+
+        if weight_sum == 0. { 0. } else { total / weight_sum }
+    }
+}
+
+#[test]
+fn test_pyramid_localization() {
+    use image::{GrayImage, Luma};
+    use HasherConfig;
+
+    let img = GrayImage::from_fn(200, 200, |x, y| {
+        Luma([((x * 3 + y * 5) % 256) as u8 ^ (x / 7) as u8])
+    });
+    let mut edited = img.clone();
+    // scribble over the top-left quadrant
+    for (x, y, px) in edited.enumerate_pixels_mut() {
+        if x < 100 && y < 100 {
+            px[0] = ((x * y) % 256) as u8;
+        }
+    }
+
+    let hasher = HasherConfig::new().to_hasher();
+    let hash = hasher.hash_image_pyramid(&img, 3);
+    let edited_hash = hasher.hash_image_pyramid(&edited, 3);
+
+    let sizes: Vec<usize> = hash.levels().iter().map(|level| level.len()).collect();
+    assert_eq!(sizes, [1, 4, 16]);
+    assert_eq!(hash.dist(&hash, &[1., 1., 1.]), 0.);
+
+    // only the cells in the top-left quadrant change
+    for (i, (l, r)) in hash.levels()[2].iter().zip(&edited_hash.levels()[2]).enumerate() {
+        assert_eq!(l.dist(r) > 0, i % 4 < 2 && i / 4 < 2, "cell {}", i);
+    }
+
+    assert!(hash.dist(&edited_hash, &[1., 1., 1.]) > 0.);
+}
@@ -0,0 +1,260 @@
+// Based on the radial variance hash from pHash (`ph_image_digest()`):
+// https://www.phash.org/docs/pubs/thesis_zauner.pdf
+use rustdct::DCTplanner;
+
+use {Image, InvalidBytesError};
+
+use std::f32::consts::PI;
+
+/// Number of DCT coefficients kept in the hash, as in pHash.
+const NUM_COEFFS: usize = 40;
+
+/// Parameters for [`RadialHash`](struct.RadialHash.html).
+///
+/// The defaults match those of pHash.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RadialParams {
+    /// The sigma of the Gaussian blur applied to the grayscale image.
+    pub sigma: f32,
+    /// The gamma correction applied to the blurred image after it is normalized to `[0, 1]`.
+    pub gamma: f32,
+    /// The number of projection lines through the center of the image.
+    ///
+    /// The lines are spaced evenly over 180 degrees. Must be at least
+    /// 40 (the number of coefficients in the hash).
+    pub projections: u32,
+}
+
+impl Default for RadialParams {
+    fn default() -> Self {
+        RadialParams {
+            sigma: 1.0,
+            gamma: 1.0,
+            projections: 180,
+        }
+    }
+}
+
+/// The radial variance hash of an image, calculated from projections through its center.
+///
+/// The image is converted to grayscale and blurred, then the variance of the luminance along
+/// lines through the center of the image at evenly spaced angles is computed. The DCT of these
+/// variances is taken and the first 40 coefficients are quantized to bytes.
+///
+/// Because the features are taken along radial lines, rotating the image by a multiple of the
+/// angle between projections (1 degree by default) cyclically shifts the variances. The DCT
+/// coefficients are not simply shifted along with them, however, so the hash only tolerates small
+/// rotations to the extent that the low-frequency coefficients change little; it is not
+/// rotation-invariant.
+///
+/// These hashes are compared by [peak cross-correlation](#method.cross_correlation)
+/// instead of Hamming distance.
+///
+/// Further Reading:
+/// https://www.phash.org/docs/pubs/thesis_zauner.pdf
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct RadialHash {
+    coeffs: [u8; NUM_COEFFS],
+}
+
+impl RadialHash {
+    /// Calculate the radial variance hash of an image with the default parameters.
+    pub fn from_image<I: Image>(img: &I) -> RadialHash {
+        Self::from_image_params(img, &RadialParams::default())
+    }
+
+    /// Calculate the radial variance hash of an image with the given parameters.
+    ///
+    /// ### Panics
+    /// If `params.projections` is less than 40.
+    pub fn from_image_params<I: Image>(img: &I, params: &RadialParams) -> RadialHash {
+        let projections = params.projections as usize;
+
+        assert!(projections >= NUM_COEFFS, "need at least {} projections, got {}",
+                NUM_COEFFS, projections);
+
+        let blurred = img.to_grayscale().blur(params.sigma);
+
+        let max = blurred.iter().cloned().max().unwrap_or(0).max(1) as f32;
+        let luma: Vec<f32> = blurred.iter().map(|&x| (x as f32 / max).powf(params.gamma)).collect();
+
+        let (width, height) = blurred.dimensions();
+        let mut features = radial_variances(&luma, width as usize, height as usize, projections);
+
+        let mut coeffs = vec![0f32; projections];
+        DCTplanner::new().plan_dct2(projections).process_dct2(&mut features, &mut coeffs);
+
+        // orthonormal scaling
+        coeffs[0] *= (1. / projections as f32).sqrt();
+        coeffs[1..].iter_mut().for_each(|c| *c *= (2. / projections as f32).sqrt());
+        coeffs.truncate(NUM_COEFFS);
+
+        let min = coeffs.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = coeffs.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+
+        let mut hash = RadialHash { coeffs: [0; NUM_COEFFS] };
+
+        if max > min {
+            for (out, &c) in hash.coeffs.iter_mut().zip(&coeffs) {
+                *out = (255. * (c - min) / (max - min)) as u8;
+            }
+        }
+
+        hash
+    }
+
+    /// Calculate the peak cross-correlation between this hash and `other`, in `[-1, 1]`.
+    ///
+    /// The correlation is calculated for every circular shift of `other`'s coefficients and the
+    /// largest value is returned. Identical images produce `1.0`; pHash considers images with
+    /// a value above `0.9` to be similar.
+    pub fn cross_correlation(&self, other: &Self) -> f32 {
+        let len = NUM_COEFFS as f32;
+
+        let mean_x = self.coeffs.iter().map(|&x| x as f32).sum::<f32>() / len;
+        let mean_y = other.coeffs.iter().map(|&y| y as f32).sum::<f32>() / len;
+
+        let xs: Vec<f32> = self.coeffs.iter().map(|&x| x as f32 - mean_x).collect();
+        let ys: Vec<f32> = other.coeffs.iter().map(|&y| y as f32 - mean_y).collect();
+
+        let den = (xs.iter().map(|x| x * x).sum::<f32>() * ys.iter().map(|y| y * y).sum::<f32>())
+            .sqrt();
+
+        // one of the hashes is constant so the correlation is undefined
+        if den == 0. {
+            return if self == other { 1. } else { 0. };
+        }
+
+        (0 .. NUM_COEFFS)
+            .map(|shift| {
+                let num: f32 = xs.iter().zip(ys.iter().cycle().skip(NUM_COEFFS - shift))
+                    .map(|(x, y)| x * y)
+                    .sum();
+                num / den
+            })
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    /// Get the bytes of this hash.
+    pub fn as_bytes(&self) -> &[u8] { &self.coeffs }
+
+    /// Create a `RadialHash` instance from the given bytes.
+    ///
+    /// ## Errors:
+    /// Returns a `InvalidBytesError::BytesWrongLength` error if the slice is not 40 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RadialHash, InvalidBytesError> {
+        if bytes.len() != NUM_COEFFS {
+            return Err(InvalidBytesError::BytesWrongLength {
+                expected: NUM_COEFFS,
+                found: bytes.len(),
+            });
+        }
+
+        let mut hash = RadialHash { coeffs: [0; NUM_COEFFS] };
+        hash.coeffs.copy_from_slice(bytes);
+        Ok(hash)
+    }
+
+    /// Create a `RadialHash` instance from the given Base64-encoded string.
+    ///
+    /// ## Errors:
+    /// Returns `InvalidBytesError::Base64` if the string wasn't valid base64.
+    /// Otherwise returns the same errors as `from_bytes`.
+    pub fn from_base64(encoded_hash: &str) -> Result<RadialHash, InvalidBytesError> {
+        let bytes = ::base64::decode(encoded_hash).map_err(InvalidBytesError::Base64)?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Get a Base64 string representing the bytes of this hash.
+    pub fn to_base64(&self) -> String {
+        ::base64::encode(&self.coeffs[..])
+    }
+}
+
+/// Calculate the variance of the luminance along `projections` lines through the center.
+fn radial_variances(luma: &[f32], width: usize, height: usize, projections: usize) -> Vec<f32> {
+    let (center_x, center_y) = (width as f32 / 2., height as f32 / 2.);
+    // long enough to reach the corners from the center
+    let radius = ((center_x * center_x + center_y * center_y).sqrt()).ceil() as i32;
+
+    (0 .. projections).map(|k| {
+        let theta = k as f32 * PI / projections as f32;
+        let (sin, cos) = theta.sin_cos();
+
+        let (mut sum, mut sum_sqd, mut count) = (0f32, 0f32, 0u32);
+
+        for t in -radius ..= radius {
+            let x = (center_x + t as f32 * cos).floor();
+            let y = (center_y + t as f32 * sin).floor();
+
+            if x >= 0. && y >= 0. && (x as usize) < width && (y as usize) < height {
+                let val = luma[y as usize * width + x as usize];
+                sum += val;
+                sum_sqd += val * val;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return 0.;
+        }
+
+        let count = count as f32;
+        sum_sqd / count - (sum * sum) / (count * count)
+    }).collect()
+}
+
+#[test]
+fn test_cross_correlation_shift() {
+    let mut bytes: Vec<u8> = (0 .. NUM_COEFFS as u8).map(|x| x * 5 % 37 * 6).collect();
+    let hash = RadialHash::from_bytes(&bytes).unwrap();
+    assert!((hash.cross_correlation(&hash) - 1.).abs() < 0.0001);
+
+    bytes.rotate_left(7);
+    let shifted = RadialHash::from_bytes(&bytes).unwrap();
+    assert!((hash.cross_correlation(&shifted) - 1.).abs() < 0.0001);
+}
+
+#[test]
+fn test_radial_hash_image() {
+    use image::{imageops, GrayImage, Luma};
+
+    // a bright bar and a dark disc off to one side, so the projections differ by angle
+    let img = GrayImage::from_fn(128, 128, |x, y| {
+        let (x, y) = (x as i32, y as i32);
+        let bar = (x - y).abs() < 10 && x > 20 && x < 100;
+        let disc = (x - 90) * (x - 90) + (y - 40) * (y - 40) < 300;
+        Luma([if bar { 220 } else if disc { 30 } else { 100 + (y / 8) as u8 }])
+    });
+
+    // rotate around the center with nearest-neighbor sampling, leaving the corners gray
+    let rotate = |img: &GrayImage, degrees: f32| GrayImage::from_fn(128, 128, |x, y| {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (x, y) = (x as f32 - 64., y as f32 - 64.);
+        let (src_x, src_y) = (x * cos + y * sin + 64., y * cos - x * sin + 64.);
+
+        if src_x >= 0. && src_y >= 0. && src_x < 128. && src_y < 128. {
+            *img.get_pixel(src_x as u32, src_y as u32)
+        } else {
+            Luma([100])
+        }
+    });
+
+    let hash = RadialHash::from_image(&img);
+
+    let mut altered = imageops::blur(&rotate(&img, 3.), 1.);
+    altered.iter_mut().for_each(|x| *x = x.saturating_add(30));
+    let similar = hash.cross_correlation(&RadialHash::from_image(&altered));
+
+    // pHash's threshold for similar images
+    assert!(similar > 0.9, "{}", similar);
+
+    for other in &[
+        GrayImage::from_fn(128, 128, |x, y| Luma([((x * y) % 256) as u8])),
+        GrayImage::from_fn(128, 128, |x, y| Luma([((x * 3 + y) % 256) as u8])),
+    ] {
+        let unrelated = hash.cross_correlation(&RadialHash::from_image(other));
+        assert!(unrelated < 0.9, "{} vs. {}", unrelated, similar);
+    }
+}
@@ -0,0 +1,66 @@
+// Based on the ring partition described by Tang et al., "Robust Image Hashing With Ring Partition
+// and Invariant Vector Distance" (2016)
+
+/// Calculate the side length of the square the image is resized to for a hash of `bits` bits.
+///
+/// This gives each ring an area of at least 50 pixels or so.
+pub fn resize_side(bits: u32) -> u32 {
+    let rings = (bits + 1) as f32;
+    8 * rings.sqrt().ceil() as u32
+}
+
+/// Calculate `bits` bits by comparing the mean luminance of each ring of the inscribed circle
+/// of the square image with the next ring out.
+///
+/// The circle is divided into `bits + 1` rings of equal area; the corners are ignored.
+pub fn ring_hash(luma: &[u8], side: usize, bits: usize) -> Vec<bool> {
+    let rings = bits + 1;
+    let radius = side as f32 / 2.;
+
+    let mut sums = vec![0u64; rings];
+    let mut counts = vec![0u64; rings];
+
+    for (i, &val) in luma.iter().enumerate() {
+        // measure from the center of the pixel
+        let x = (i % side) as f32 + 0.5 - radius;
+        let y = (i / side) as f32 + 0.5 - radius;
+
+        // the area inside a circle is proportional to its radius squared
+        let area = (x * x + y * y) / (radius * radius);
+
+        if area < 1. {
+            let ring = ((area * rings as f32) as usize).min(rings - 1);
+            sums[ring] += val as u64;
+            counts[ring] += 1;
+        }
+    }
+
+    let means: Vec<f32> = sums.iter().zip(&counts)
+        .map(|(&sum, &count)| sum as f32 / count.max(1) as f32)
+        .collect();
+
+    means.windows(2).map(|pair| pair[0] < pair[1]).collect()
+}
+
+#[test]
+fn test_ring_hash_rotation() {
+    use image::{GrayImage, Luma};
+    use {HashAlg, HasherConfig};
+
+    // rings of varying brightness with a pattern around the center that rotates with the image
+    let render = |angle: f32| GrayImage::from_fn(128, 128, |x, y| {
+        let (x, y) = (x as f32 - 64., y as f32 - 64.);
+        let (r, theta) = ((x * x + y * y).sqrt(), y.atan2(x) + angle);
+        Luma([(128. + 60. * (r / 5.).sin() + 40. * (3. * theta).cos()) as u8])
+    });
+
+    let hasher = HasherConfig::new().hash_alg(HashAlg::RingPartition).to_hasher();
+    let hash = hasher.hash_image(&render(0.));
+
+    assert_eq!(hash.as_bytes().len(), 8);
+    assert!(hash.dist(&hasher.hash_image(&render(0.3))) <= 2);
+    assert!(hash.dist(&hasher.hash_image(&render(2.))) <= 2);
+
+    let other = GrayImage::from_fn(128, 128, |x, y| Luma([((x * 3 + y) % 256) as u8]));
+    assert!(hash.dist(&hasher.hash_image(&other)) > 16);
+}
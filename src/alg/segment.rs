@@ -0,0 +1,196 @@
+// Based on `crop_resistant_hash()` from Python's `imagehash`, which is in turn based on
+// Jain et al., "Image Hashing for Tamper Detection with Multiview Embedding" (2018):
+// https://github.com/JohannesBuchner/imagehash
+use image::{imageops, GrayImage};
+
+use {FilterType, HashBytes, ImageHash};
+
+use std::cmp::Reverse;
+
+/// Parameters for segmenting images with
+/// [`Hasher::hash_image_segments()`](struct.Hasher.html#method.hash_image_segments).
+///
+/// The defaults match those of `imagehash`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentParams {
+    /// Pixels brighter than this are part of bright segments, the rest are part of dark segments.
+    pub threshold: u8,
+    /// Segments must have more than this many pixels in the segmentation image to be hashed.
+    pub min_segment_size: u32,
+    /// The side of the square that the image is resized to for segmentation.
+    pub segmentation_size: u32,
+    /// If set, only the given number of largest segments are hashed.
+    pub max_segments: Option<u32>,
+}
+
+impl Default for SegmentParams {
+    fn default() -> Self {
+        SegmentParams {
+            threshold: 128,
+            min_segment_size: 500,
+            segmentation_size: 300,
+            max_segments: None,
+        }
+    }
+}
+
+/// A set of hashes of different parts of an image, such as the segments hashed by
+/// [`Hasher::hash_image_segments()`](struct.Hasher.html#method.hash_image_segments).
+///
+/// Two multi-hashes are compared by counting how many of the hashes of one have a close match
+/// in the other, so images can be matched as long as some of their parts survive.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct MultiHash<B = Box<[u8]>> {
+    hashes: Vec<ImageHash<B>>,
+}
+
+impl<B: HashBytes> MultiHash<B> {
+    /// Create a `MultiHash` from the given hashes, e.g. to restore stored hashes.
+    pub fn from_hashes(hashes: Vec<ImageHash<B>>) -> Self {
+        MultiHash { hashes }
+    }
+
+    /// Get the hashes in this set.
+    pub fn as_slice(&self) -> &[ImageHash<B>] { &self.hashes }
+
+    /// Find the closest hash in `other` for each hash in this set, and count and sum the
+    /// distances of those within `max_dist`.
+    ///
+    /// Returns `(matches, total_dist)`. `imagehash` uses a `max_dist` of a quarter of the bits
+    /// of each hash by default; more matches and then a smaller total distance indicate more
+    /// similar images.
+    pub fn diff(&self, other: &Self, max_dist: u32) -> (usize, u32) {
+        self.hashes.iter()
+            .filter_map(|hash| other.hashes.iter().map(|other| hash.dist(other)).min())
+            .filter(|&dist| dist <= max_dist)
+            .fold((0, 0), |(matches, total), dist| (matches + 1, total + dist))
+    }
+
+    /// Check if at least `min_matches` hashes in this set have a match in `other` within
+    /// `max_dist`.
+    pub fn matches(&self, other: &Self, max_dist: u32, min_matches: usize) -> bool {
+        self.diff(other, max_dist).0 >= min_matches
+    }
+}
+
+/// Segment the image and return the bounding boxes of the segments that are large enough
+/// as `[x, y, width, height]` in the coordinates of `img`.
+pub fn segment_bounds(img: &GrayImage, params: &SegmentParams, filter: FilterType) -> Vec<[u32; 4]> {
+    let side = params.segmentation_size;
+    let small = imageops::resize(img, side, side, filter);
+    let small = median_filter(&imageops::blur(&small, 2.0));
+
+    let side = side as usize;
+    let bright: Vec<bool> = small.iter().map(|&x| x > params.threshold).collect();
+
+    // `(size, [min_x, min_y, max_x, max_y])`
+    let mut segments = Vec::new();
+    let mut assigned = vec![false; bright.len()];
+    let mut stack = Vec::new();
+
+    for start in 0 .. bright.len() {
+        if assigned[start] {
+            continue;
+        }
+
+        // flood fill the 4-connected region of the same brightness
+        let is_bright = bright[start];
+        let (mut size, mut bounds) = (0u32, [side, side, 0, 0]);
+
+        assigned[start] = true;
+        stack.push(start);
+
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % side, i / side);
+
+            size += 1;
+            bounds = [bounds[0].min(x), bounds[1].min(y), bounds[2].max(x), bounds[3].max(y)];
+
+            let neighbors = [
+                if x > 0 { Some(i - 1) } else { None },
+                if x + 1 < side { Some(i + 1) } else { None },
+                if y > 0 { Some(i - side) } else { None },
+                if y + 1 < side { Some(i + side) } else { None },
+            ];
+
+            for n in neighbors.iter().filter_map(|&n| n) {
+                if !assigned[n] && bright[n] == is_bright {
+                    assigned[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+
+        if size > params.min_segment_size {
+            segments.push((size, bounds));
+        }
+    }
+
+    if let Some(max_segments) = params.max_segments {
+        segments.sort_by_key(|&(size, _)| Reverse(size));
+        segments.truncate(max_segments as usize);
+    }
+
+    let (width, height) = img.dimensions();
+    let scale_x = width as f32 / side as f32;
+    let scale_y = height as f32 / side as f32;
+
+    segments.into_iter().map(|(_, [min_x, min_y, max_x, max_y])| {
+        let left = (min_x as f32 * scale_x) as u32;
+        let top = (min_y as f32 * scale_y) as u32;
+        let right = (((max_x + 1) as f32 * scale_x).ceil() as u32).min(width).max(left + 1);
+        let bottom = (((max_y + 1) as f32 * scale_y).ceil() as u32).min(height).max(top + 1);
+
+        [left, top, right - left, bottom - top]
+    }).collect()
+}
+
+/// Replace each pixel with the median of its 3 x 3 neighborhood, clamping at the edges.
+fn median_filter(img: &GrayImage) -> GrayImage {
+    let (width, height) = img.dimensions();
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let mut window = [0u8; 9];
+
+        for (i, val) in window.iter_mut().enumerate() {
+            let wx = (x + (i % 3) as u32).saturating_sub(1).min(width - 1);
+            let wy = (y + (i / 3) as u32).saturating_sub(1).min(height - 1);
+            *val = img.get_pixel(wx, wy)[0];
+        }
+
+        window.sort_unstable();
+        [window[4]].into()
+    })
+}
+
+#[test]
+fn test_crop_resistant() {
+    use image::Luma;
+    use HasherConfig;
+
+    // bright textured blobs on a dark background
+    let blobs = [(80, 70, 50), (300, 90, 60), (120, 220, 45), (310, 230, 40)];
+    let img = GrayImage::from_fn(400, 300, |x, y| {
+        let in_blob = blobs.iter().any(|&(cx, cy, r)| {
+            let (dx, dy) = (x as i32 - cx, y as i32 - cy);
+            dx * dx + dy * dy < r * r
+        });
+
+        Luma([if in_blob { (150 + (x * 7 + y * 3) % 100) as u8 } else { (y / 10) as u8 }])
+    });
+
+    let hasher = HasherConfig::new().to_hasher();
+    let hash = hasher.hash_image_segments(&img);
+
+    // all four blobs and the background
+    assert_eq!(hash.as_slice().len(), 5);
+
+    let cropped = imageops::crop_imm(&img, 200, 0, 200, 300).to_image();
+    let cropped_hash = hasher.hash_image_segments(&cropped);
+
+    let (matches, _) = cropped_hash.diff(&hash, 16);
+    assert!(matches >= 2, "{} matches", matches);
+
+    let other = GrayImage::from_fn(400, 300, |x, y| Luma([((x * y) % 256) as u8]));
+    assert!(hasher.hash_image_segments(&other).diff(&hash, 16).0 < matches);
+}
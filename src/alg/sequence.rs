@@ -0,0 +1,167 @@
+use {HashBytes, ImageHash};
+
+use std::time::Duration;
+
+/// The hash of one keyframe of an animation.
+///
+/// Part of [`SequenceHash`](struct.SequenceHash.html).
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Keyframe<B = Box<[u8]>> {
+    /// The time from the start of the animation that the keyframe is first shown.
+    pub timestamp: Duration,
+    /// The hash of the keyframe.
+    pub hash: ImageHash<B>,
+}
+
+/// The hashes of the keyframes of an animation.
+///
+/// Get an instance with [`Hasher::hash_frames()`](struct.Hasher.html#method.hash_frames) or
+/// [`Hasher::hash_animation()`](struct.Hasher.html#method.hash_animation).
+///
+/// Consecutive frames with similar hashes are merged into one keyframe, so the same
+/// animation at a different frame rate produces (nearly) the same keyframes.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct SequenceHash<B = Box<[u8]>> {
+    keyframes: Vec<Keyframe<B>>,
+    duration: Duration,
+}
+
+impl<B: HashBytes> SequenceHash<B> {
+    /// Create a `SequenceHash` from the given keyframes and the total duration of the animation,
+    /// e.g. to restore stored hashes.
+    pub fn from_keyframes(keyframes: Vec<Keyframe<B>>, duration: Duration) -> Self {
+        SequenceHash { keyframes, duration }
+    }
+
+    /// Get the keyframes in order.
+    pub fn keyframes(&self) -> &[Keyframe<B>] { &self.keyframes }
+
+    /// Get the total duration of the animation.
+    pub fn duration(&self) -> Duration { self.duration }
+
+    /// Calculate the distance between this sequence and `other`, ignoring the timestamps.
+    ///
+    /// Each keyframe of the sequence with fewer keyframes is matched with a keyframe of the
+    /// other sequence, in order, so that the mean Hamming distance of the matches is as small as
+    /// possible; that mean is returned. Keyframes at the start and end of the longer sequence
+    /// may go unmatched, so trimming an animation doesn't increase its distance from the
+    /// original.
+    ///
+    /// Returns `0.0` if both sequences are empty and infinity if only one is.
+    pub fn dist(&self, other: &Self) -> f32 {
+        let (short, long) = if self.keyframes.len() <= other.keyframes.len() {
+            (&self.keyframes, &other.keyframes)
+        } else {
+            (&other.keyframes, &self.keyframes)
+        };
+
+        if short.is_empty() {
+            return if long.is_empty() { 0. } else { f32::INFINITY };
+        }
+
+        // the smallest total distance of matching the keyframes of `short` so far,
+        // with the last one matched to each keyframe of `long`
+        let mut totals = vec![0u32; long.len()];
+
+        for frame in short {
+            let mut best_prev = u32::MAX;
+
+            for (total, other) in totals.iter_mut().zip(long.iter()) {
+                // the previous keyframe can match this keyframe of `long` or any before it
+                best_prev = best_prev.min(*total);
+                *total = best_prev + frame.hash.dist(&other.hash);
+            }
+        }
+
+        *totals.iter().min().expect("no keyframes") as f32 / short.len() as f32
+    }
+}
+
+/// Merge frames into keyframes, starting a new keyframe when a frame's hash is further
+/// than `max_dist` from the hash of the current keyframe.
+pub fn keyframes<B, I>(frames: I, max_dist: u32) -> SequenceHash<B>
+where B: HashBytes, I: IntoIterator<Item = (ImageHash<B>, Duration)> {
+    let mut keyframes: Vec<Keyframe<B>> = Vec::new();
+    let mut timestamp = Duration::from_secs(0);
+
+    for (hash, delay) in frames {
+        let is_static = keyframes.last().map_or(false, |last| last.hash.dist(&hash) <= max_dist);
+
+        if !is_static {
+            keyframes.push(Keyframe { timestamp, hash });
+        }
+
+        timestamp += delay;
+    }
+
+    SequenceHash { keyframes, duration: timestamp }
+}
+
+#[test]
+fn test_sequence_dist() {
+    use image::{GrayImage, Luma};
+    use HasherConfig;
+
+    let hasher = HasherConfig::new().to_hasher();
+
+    // a bar moving across the frame
+    let frames: Vec<GrayImage> = (0 .. 8).map(|i| GrayImage::from_fn(64, 64, |x, _| {
+        Luma([if x / 8 == i { 255 } else { x as u8 }])
+    })).collect();
+
+    let frame_ms = |ms| Duration::from_millis(ms);
+
+    let seq = hasher.hash_frames(frames.iter().map(|f| (f.clone(), frame_ms(100))), 0);
+    assert_eq!(seq.keyframes().len(), 8);
+    assert_eq!(seq.keyframes()[2].timestamp, frame_ms(200));
+    assert_eq!(seq.duration(), frame_ms(800));
+
+    // at double the frame rate every frame is shown twice
+    let doubled = hasher.hash_frames(frames.iter().flat_map(|f| vec![(f.clone(), frame_ms(50)); 2]), 0);
+    assert_eq!(doubled.keyframes().len(), 8);
+    assert_eq!(seq.dist(&doubled), 0.);
+
+    let trimmed = hasher.hash_frames(frames[2 .. 6].iter().map(|f| (f.clone(), frame_ms(100))), 0);
+    assert_eq!(seq.dist(&trimmed), 0.);
+    assert_eq!(trimmed.dist(&seq), 0.);
+
+    let reversed = hasher.hash_frames(frames.iter().rev().map(|f| (f.clone(), frame_ms(100))), 0);
+    assert!(seq.dist(&reversed) > 0.);
+}
+
+#[test]
+fn test_hash_animation() {
+    use image::{AnimationDecoder, Delay, Frame, Frames, ImageError, RgbaImage};
+    use image::error::{DecodingError, ImageFormatHint};
+    use HasherConfig;
+
+    struct Decoder(usize);
+
+    impl<'a> AnimationDecoder<'a> for Decoder {
+        fn into_frames(self) -> Frames<'a> {
+            let fail_at = self.0;
+
+            // frames must be decoded lazily, so nothing after an error is pulled
+            Frames::new(Box::new((0 .. 4).map(move |i| {
+                assert!(i <= fail_at, "frame {} decoded after an error", i);
+
+                if i == fail_at {
+                    return Err(ImageError::Decoding(DecodingError::new(
+                        ImageFormatHint::Unknown, "corrupt frame")));
+                }
+
+                let buffer = RgbaImage::from_fn(32, 32, |x, _| {
+                    [(x * i as u32 * 2) as u8, 0, 0, 255].into()
+                });
+                Ok(Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(100, 1)))
+            })))
+        }
+    }
+
+    let hasher = HasherConfig::new().to_hasher();
+
+    let seq = hasher.hash_animation(Decoder(4), 0).unwrap();
+    assert_eq!(seq.duration(), Duration::from_millis(400));
+
+    assert!(hasher.hash_animation(Decoder(2), 0).is_err());
+}
@@ -0,0 +1,91 @@
+// Random hyperplane locality-sensitive hashing as described by Charikar, "Similarity Estimation
+// Techniques from Rounding Algorithms" (2002)
+use std::f64::consts::PI;
+
+/// Random Gaussian hyperplanes through the origin, one per bit of the hash.
+pub struct SimHashPlanes {
+    planes: Vec<f32>,
+    dims: usize,
+}
+
+impl SimHashPlanes {
+    /// Generate `bits` hyperplanes in `dims` dimensions from `seed`.
+    pub fn new(bits: u32, dims: usize, seed: u64) -> Self {
+        assert!(bits > 0, "SimHash needs at least one bit");
+
+        let mut rng = SplitMix64(seed);
+
+        // Box-Muller transform, two normally distributed values per pair of uniform ones
+        let planes = (0 .. (bits as usize * dims + 1) / 2).flat_map(|_| {
+            // in `(0, 1]` so the log is finite
+            let u1 = 1. - rng.next_f64();
+            let u2 = rng.next_f64();
+
+            let radius = (-2. * u1.ln()).sqrt();
+            let (sin, cos) = (2. * PI * u2).sin_cos();
+            vec![(radius * cos) as f32, (radius * sin) as f32]
+        }).take(bits as usize * dims).collect();
+
+        SimHashPlanes { planes, dims }
+    }
+
+    /// The number of bits in the hash.
+    pub fn bits(&self) -> usize {
+        self.planes.len() / self.dims
+    }
+
+    /// Center the values on their mean and set a bit for each hyperplane they're on the
+    /// positive side of.
+    ///
+    /// ### Panics
+    /// If the number of values is not the number of dimensions of the hyperplanes.
+    pub fn hash<'a>(&'a self, vals: &[f32]) -> impl Iterator<Item = bool> + 'a {
+        assert_eq!(vals.len(), self.dims, "wrong number of values for SimHash");
+
+        let mean = vals.iter().sum::<f32>() / vals.len() as f32;
+        let centered: Vec<f32> = vals.iter().map(|&x| x - mean).collect();
+
+        self.planes.chunks(self.dims).map(move |plane| {
+            plane.iter().zip(&centered).map(|(p, x)| p * x).sum::<f32>() > 0.
+        })
+    }
+}
+
+/// A small, fast PRNG so the hyperplanes are the same everywhere for a given seed.
+///
+/// http://xoshiro.di.unimi.it/splitmix64.c
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed value in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[test]
+fn test_simhash_planes() {
+    let planes = SimHashPlanes::new(100, 64, 42);
+    assert_eq!(planes.bits(), 100);
+    assert_eq!(planes.planes, SimHashPlanes::new(100, 64, 42).planes);
+    assert!(planes.planes != SimHashPlanes::new(100, 64, 43).planes);
+
+    // roughly standard normal
+    let mean = planes.planes.iter().sum::<f32>() / planes.planes.len() as f32;
+    let var = planes.planes.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>()
+        / planes.planes.len() as f32;
+    assert!(mean.abs() < 0.05 && (var - 1.).abs() < 0.1, "mean {} var {}", mean, var);
+
+    // inverting the values puts them on the other side of every hyperplane
+    let vals: Vec<f32> = (0 .. 64).map(|x| (x * 37 % 64) as f32).collect();
+    let inverted: Vec<f32> = vals.iter().map(|x| 255. - x).collect();
+    assert!(planes.hash(&vals).zip(planes.hash(&inverted)).all(|(l, r)| l != r));
+}
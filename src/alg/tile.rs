@@ -0,0 +1,109 @@
+use {HashBytes, ImageHash};
+
+/// The hash of one tile of an image, with its position.
+///
+/// Part of [`TileHashes`](struct.TileHashes.html).
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct TileHash<B = Box<[u8]>> {
+    /// The column of the tile in the grid, starting from the left.
+    pub column: u32,
+    /// The row of the tile in the grid, starting from the top.
+    pub row: u32,
+    /// The bounds of the tile in the image, as `[x, y, width, height]`.
+    pub bounds: [u32; 4],
+    /// The hash of the tile.
+    pub hash: ImageHash<B>,
+}
+
+/// The hashes of the tiles of an image split into a grid.
+///
+/// Get an instance with
+/// [`Hasher::hash_image_tiles()`](struct.Hasher.html#method.hash_image_tiles).
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct TileHashes<B = Box<[u8]>> {
+    tiles: Vec<TileHash<B>>,
+}
+
+impl<B: HashBytes> TileHashes<B> {
+    /// Create a `TileHashes` from the given tiles, e.g. to restore stored hashes.
+    pub fn from_tiles(tiles: Vec<TileHash<B>>) -> Self {
+        TileHashes { tiles }
+    }
+
+    /// Get the tiles in row-major order.
+    pub fn as_slice(&self) -> &[TileHash<B>] { &self.tiles }
+
+    /// Find every pair of tiles from this set and `other` whose hashes are within `max_dist`.
+    ///
+    /// Returns `(self_index, other_index, dist)` for each pair, where the indices are into
+    /// [`as_slice()`](#method.as_slice) of each set.
+    ///
+    /// To find a known image embedded in another, compare the tiles of the larger image with
+    /// a single-tile set of the known image; to find the parts of a collage, compare with the
+    /// hashes of each of the known images.
+    pub fn matching_tiles(&self, other: &Self, max_dist: u32) -> Vec<(usize, usize, u32)> {
+        self.tiles.iter().enumerate().flat_map(|(i, tile)| {
+            other.tiles.iter().enumerate().filter_map(move |(j, other)| {
+                let dist = tile.hash.dist(&other.hash);
+                if dist <= max_dist { Some((i, j, dist)) } else { None }
+            })
+        }).collect()
+    }
+}
+
+/// Calculate the bounds of the tiles in row-major order as `[x, y, width, height]`.
+///
+/// Adjacent tiles share `overlap` of their width or height.
+///
+/// ### Panics
+/// If `columns` or `rows` is 0 or `overlap` is not in `[0, 1)`.
+pub fn tile_bounds(width: u32, height: u32, columns: u32, rows: u32, overlap: f32) -> Vec<[u32; 4]> {
+    assert!(columns > 0 && rows > 0, "grid must have at least one tile: {} x {}", columns, rows);
+    assert!((0. .. 1.).contains(&overlap), "overlap must be in [0, 1): {}", overlap);
+
+    // the start and end of each tile along one dimension
+    let spans = |len: u32, count: u32| -> Vec<(u32, u32)> {
+        let tile = len as f32 / (count as f32 - (count - 1) as f32 * overlap);
+        let step = tile * (1. - overlap);
+
+        (0 .. count).map(|i| {
+            let start = ((i as f32 * step).round() as u32).min(len.saturating_sub(1));
+            let end = ((i as f32 * step + tile).round() as u32).min(len).max(start + 1);
+            (start, end - start)
+        }).collect()
+    };
+
+    let (xs, ys) = (spans(width, columns), spans(height, rows));
+
+    ys.iter().flat_map(|&(y, tile_height)| {
+        xs.iter().map(move |&(x, tile_width)| [x, y, tile_width, tile_height])
+    }).collect()
+}
+
+#[test]
+fn test_tile_bounds() {
+    assert_eq!(tile_bounds(100, 50, 2, 1, 0.), [[0, 0, 50, 50], [50, 0, 50, 50]]);
+    // three tiles of 50 overlapping by half
+    assert_eq!(tile_bounds(100, 50, 3, 1, 0.5),
+               [[0, 0, 50, 50], [25, 0, 50, 50], [50, 0, 50, 50]]);
+}
+
+#[test]
+fn test_collage() {
+    use image::{imageops, GrayImage, Luma};
+    use HasherConfig;
+
+    let left = GrayImage::from_fn(64, 64, |x, y| Luma([(x * 4) as u8 ^ (y * 2) as u8]));
+    let right = GrayImage::from_fn(64, 64, |x, y| Luma([((x * y) % 256) as u8]));
+
+    let mut collage = GrayImage::new(128, 64);
+    imageops::replace(&mut collage, &left, 0, 0);
+    imageops::replace(&mut collage, &right, 64, 0);
+
+    let hasher = HasherConfig::new().to_hasher();
+    let tiles = hasher.hash_image_tiles(&collage, 2, 1, 0.);
+    assert_eq!(tiles.as_slice()[1].bounds, [64, 0, 64, 64]);
+
+    let known = hasher.hash_image_tiles(&right, 1, 1, 0.);
+    assert_eq!(tiles.matching_tiles(&known, 4), [(1, 0, 0)]);
+}
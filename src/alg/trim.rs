@@ -0,0 +1,103 @@
+use image::{Rgba, RgbaImage};
+
+/// Parameters for trimming borders with
+/// [`HasherConfig::trim_borders()`](struct.HasherConfig.html#method.trim_borders).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrimParams {
+    /// The largest difference in any channel (including alpha) from the color of a border
+    /// for a pixel to still be considered part of it, to allow for noise and compression
+    /// artifacts.
+    pub tolerance: u8,
+    /// The smallest fraction of the width and height of the image that must remain after
+    /// trimming. If the content is smaller than this in either dimension, nothing is trimmed.
+    pub min_content: f32,
+}
+
+impl Default for TrimParams {
+    fn default() -> Self {
+        TrimParams {
+            tolerance: 16,
+            min_content: 0.25,
+        }
+    }
+}
+
+/// Find the bounds of the image inside any uniform borders, as `[x, y, width, height]`.
+///
+/// Each side of the image is trimmed for as long as each row or column is uniformly the color
+/// of the pixel in the middle of the outermost one.
+pub fn content_bounds(img: &RgbaImage, params: &TrimParams) -> [u32; 4] {
+    let (width, height) = img.dimensions();
+    let full = [0, 0, width, height];
+
+    if width == 0 || height == 0 {
+        return full;
+    }
+
+    let close = |l: &Rgba<u8>, r: &Rgba<u8>| {
+        l.0.iter().zip(&r.0).all(|(&l, &r)| (l as i16 - r as i16).unsigned_abs() as u8 <= params.tolerance)
+    };
+
+    let row_uniform = |y: u32, left: u32, right: u32, color: &Rgba<u8>| {
+        (left .. right).all(|x| close(img.get_pixel(x, y), color))
+    };
+
+    let col_uniform = |x: u32, top: u32, bottom: u32, color: &Rgba<u8>| {
+        (top .. bottom).all(|y| close(img.get_pixel(x, y), color))
+    };
+
+    let top_color = *img.get_pixel(width / 2, 0);
+    let top = (0 .. height).find(|&y| !row_uniform(y, 0, width, &top_color));
+
+    // the image is all one color
+    let top = match top {
+        Some(top) => top,
+        None => return full,
+    };
+
+    let bottom_color = *img.get_pixel(width / 2, height - 1);
+    let bottom = (top .. height).rev().find(|&y| !row_uniform(y, 0, width, &bottom_color))
+        .map_or(height, |y| y + 1);
+
+    let left_color = *img.get_pixel(0, (top + bottom) / 2);
+    let left = (0 .. width).find(|&x| !col_uniform(x, top, bottom, &left_color)).unwrap_or(0);
+
+    let right_color = *img.get_pixel(width - 1, (top + bottom) / 2);
+    let right = (left .. width).rev().find(|&x| !col_uniform(x, top, bottom, &right_color))
+        .map_or(width, |x| x + 1);
+
+    let (content_width, content_height) = (right - left, bottom - top);
+
+    if (content_width as f32) < width as f32 * params.min_content
+        || (content_height as f32) < height as f32 * params.min_content {
+        return full;
+    }
+
+    [left, top, content_width, content_height]
+}
+
+#[test]
+fn test_letterbox() {
+    use image::imageops;
+    use HasherConfig;
+
+    let content = RgbaImage::from_fn(60, 40, |x, y| Rgba([(x * 4) as u8, (y * 6) as u8, 90, 255]));
+
+    // black bars above and below, with a little noise
+    let mut letterboxed = RgbaImage::from_fn(80, 70, |x, y| {
+        let noise = ((x * 7 + y * 3) % 5) as u8;
+        Rgba([noise, noise, noise, 255])
+    });
+    imageops::replace(&mut letterboxed, &content, 10, 15);
+
+    assert_eq!(content_bounds(&letterboxed, &TrimParams::default()), [10, 15, 60, 40]);
+
+    let hasher = HasherConfig::new().trim_borders(TrimParams::default()).to_hasher();
+    let (hash, bounds) = hasher.hash_image_with_bounds(&letterboxed);
+    assert_eq!(bounds, [10, 15, 60, 40]);
+    assert_eq!(hash, hasher.hash_image(&content));
+
+    // too little content to trim
+    let params = TrimParams { min_content: 0.9, ..TrimParams::default() };
+    assert_eq!(content_bounds(&letterboxed, &params), [0, 0, 80, 70]);
+}
@@ -15,9 +15,14 @@ pub struct DctCtxt {
 
 impl DctCtxt {
     pub fn new(width: u32, height: u32) -> Self {
+        Self::exact(width * SIZE_MULTIPLIER, height * SIZE_MULTIPLIER)
+    }
+
+    /// Plan a DCT over exactly `width x height` values, without applying `SIZE_MULTIPLIER`.
+    pub fn exact(width: u32, height: u32) -> Self {
         let mut planner = DCTplanner::new();
-        let width = width as usize * SIZE_MULTIPLIER_U;
-        let height = height as usize * SIZE_MULTIPLIER_U;
+        let width = width as usize;
+        let height = height as usize;
 
         DctCtxt {
             row_dct: planner.plan_dct2(width),
@@ -73,6 +78,22 @@ impl DctCtxt {
     pub fn crop_2d(&self, packed: Vec<f32>) -> Vec<f32> {
         crop_2d_dct(packed, self.width)
     }
+
+    /// Take the low-frequency `width x height` block of a packed 2D DCT, skipping the first row
+    /// and column so the DC coefficient is excluded, as in the reference pHash implementation.
+    pub fn crop_low_freq(&self, packed: &[f32], width: u32, height: u32) -> Vec<f32> {
+        crop_low_freq(packed, self.width, width as usize, height as usize)
+    }
+}
+
+/// Generic for easier testing
+fn crop_low_freq<T: Copy>(packed: &[T], rowstride: usize, width: usize, height: usize) -> Vec<T> {
+    assert!(width < rowstride && height < packed.len() / rowstride,
+            "cannot crop {} x {} from DCT with rowstride {}", width, height, rowstride);
+
+    packed.chunks(rowstride).skip(1).take(height)
+        .flat_map(|row| row[1 ..= width].iter().cloned())
+        .collect()
 }
 
 /// Crop the values off a 1D-packed 2D DCT.
@@ -116,6 +137,20 @@ fn test_crop_2d_dct() {
     );
 }
 
+#[test]
+fn test_crop_low_freq() {
+    let packed: Vec<i32> = (0 .. 64).collect();
+    assert_eq!(
+        crop_low_freq(&packed, 8, 3, 2),
+        [
+            // 0, 1, 2, 3, ...
+            /* 8, */ 9, 10, 11, // 12 ..
+            /* 16, */ 17, 18, 19, // 20 ..
+            // 24 .. 64
+        ]
+    );
+}
+
 #[test]
 fn test_transpose() {
 
@@ -0,0 +1,144 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+pub struct DwtCtxt {
+    width: usize,
+    height: usize,
+    levels: usize,
+    max_levels: usize,
+    remove_ll: bool,
+}
+
+impl DwtCtxt {
+    /// Plan a Haar DWT whose LL band after `levels` levels is `width x height`.
+    ///
+    /// If `remove_ll` is set, the LL band of the deepest possible decomposition is zeroed first.
+    pub fn new(width: u32, height: u32, levels: u32, remove_ll: bool) -> Self {
+        // we can keep halving the LL band as long as both of its dimensions are even
+        let max_levels = levels + width.trailing_zeros().min(height.trailing_zeros());
+
+        DwtCtxt {
+            width: (width as usize) << levels,
+            height: (height as usize) << levels,
+            levels: levels as usize,
+            max_levels: max_levels as usize,
+            remove_ll,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height as u32
+    }
+
+    /// Perform the configured DWT on a 1D-packed vector of `width x height` and return the LL band.
+    ///
+    /// ### Panics
+    /// If `self.width * self.height != packed_2d.len()`
+    pub fn ll_band(&self, mut packed_2d: Vec<f32>) -> Vec<f32> {
+        let Self { width, height, levels, max_levels, remove_ll } = *self;
+
+        assert_eq!(width * height, packed_2d.len());
+
+        if remove_ll {
+            haar_2d(&mut packed_2d, width, height, max_levels);
+
+            for row in packed_2d.chunks_mut(width).take(height >> max_levels) {
+                row[.. width >> max_levels].iter_mut().for_each(|x| *x = 0.);
+            }
+
+            inv_haar_2d(&mut packed_2d, width, height, max_levels);
+        }
+
+        haar_2d(&mut packed_2d, width, height, levels);
+
+        packed_2d.chunks(width).take(height >> levels)
+            .flat_map(|row| row[.. width >> levels].iter().cloned())
+            .collect()
+    }
+}
+
+/// Multi-level 2D Haar DWT, in-place with the bands of each level packed into the top-left corner.
+fn haar_2d(packed: &mut [f32], rowstride: usize, height: usize, levels: usize) {
+    let mut line = Vec::with_capacity(rowstride.max(height));
+    let mut scratch = Vec::new();
+
+    for level in 0 .. levels {
+        let (width, height) = (rowstride >> level, height >> level);
+        transform_block(packed, rowstride, width, height, &mut line, &mut scratch, haar_1d);
+    }
+}
+
+/// Invert `haar_2d` with the same number of levels.
+fn inv_haar_2d(packed: &mut [f32], rowstride: usize, height: usize, levels: usize) {
+    let mut line = Vec::with_capacity(rowstride.max(height));
+    let mut scratch = Vec::new();
+
+    for level in (0 .. levels).rev() {
+        let (width, height) = (rowstride >> level, height >> level);
+        transform_block(packed, rowstride, width, height, &mut line, &mut scratch, inv_haar_1d);
+    }
+}
+
+/// Apply `transform` to the rows and then the columns of the top-left `width x height` block.
+fn transform_block(packed: &mut [f32], rowstride: usize, width: usize, height: usize,
+                   line: &mut Vec<f32>, scratch: &mut Vec<f32>, transform: fn(&mut [f32], &mut [f32])) {
+    scratch.resize(width.max(height), 0.);
+
+    for row in packed.chunks_mut(rowstride).take(height) {
+        transform(&mut row[..width], scratch);
+    }
+
+    for col in 0 .. width {
+        line.clear();
+        line.extend(packed[col..].iter().step_by(rowstride).take(height));
+
+        transform(line, scratch);
+
+        for (dest, &src) in packed[col..].iter_mut().step_by(rowstride).zip(line.iter()) {
+            *dest = src;
+        }
+    }
+}
+
+/// Orthonormal Haar step: averages in the first half of `data`, details in the second half.
+fn haar_1d(data: &mut [f32], scratch: &mut [f32]) {
+    let half = data.len() / 2;
+
+    for i in 0 .. half {
+        let (a, b) = (data[i * 2], data[i * 2 + 1]);
+        scratch[i] = (a + b) * FRAC_1_SQRT_2;
+        scratch[half + i] = (a - b) * FRAC_1_SQRT_2;
+    }
+
+    data.copy_from_slice(&scratch[..data.len()]);
+}
+
+fn inv_haar_1d(data: &mut [f32], scratch: &mut [f32]) {
+    let half = data.len() / 2;
+
+    for i in 0 .. half {
+        let (avg, detail) = (data[i], data[half + i]);
+        scratch[i * 2] = (avg + detail) * FRAC_1_SQRT_2;
+        scratch[i * 2 + 1] = (avg - detail) * FRAC_1_SQRT_2;
+    }
+
+    data.copy_from_slice(&scratch[..data.len()]);
+}
+
+#[test]
+fn test_haar_2d_roundtrip() {
+    let orig: Vec<f32> = (0 .. 64).map(|x| (x * 37 % 64) as f32).collect();
+
+    let mut packed = orig.clone();
+    haar_2d(&mut packed, 8, 8, 3);
+    // the fully decomposed LL coefficient is the sum scaled by `1/2` per level
+    assert!((packed[0] - orig.iter().sum::<f32>() / 8.).abs() < 0.001);
+
+    inv_haar_2d(&mut packed, 8, 8, 3);
+    for (l, r) in packed.iter().zip(&orig) {
+        assert!((l - r).abs() < 0.001, "{} != {}", l, r);
+    }
+}
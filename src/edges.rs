@@ -0,0 +1,172 @@
+use image::{imageops, GrayImage};
+
+/// Edge detectors for
+/// [`HasherConfig::preproc_edges()`](struct.HasherConfig.html#method.preproc_edges).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EdgeDetector {
+    /// The magnitude of the Sobel operator, scaled so the strongest edge in the image is 255.
+    ///
+    /// Further Reading:
+    /// https://en.wikipedia.org/wiki/Sobel_operator
+    Sobel,
+    /// The Canny edge detector, producing thin edges of 255 on a background of 0.
+    ///
+    /// Further Reading:
+    /// https://en.wikipedia.org/wiki/Canny_edge_detector
+    Canny {
+        /// The sigma of the Gaussian blur applied before finding the gradient.
+        sigma: f32,
+        /// Weak edges must have a gradient magnitude of at least this fraction of the strongest
+        /// edge in the image, and are only kept if they're connected to a strong edge.
+        low: f32,
+        /// Strong edges must have a gradient magnitude of at least this fraction of the strongest
+        /// edge in the image.
+        high: f32,
+    },
+}
+
+impl EdgeDetector {
+    /// The Canny edge detector with commonly used parameters: a sigma of 1.4 and thresholds of
+    /// 0.1 and 0.3.
+    pub fn canny() -> Self {
+        EdgeDetector::Canny { sigma: 1.4, low: 0.1, high: 0.3 }
+    }
+
+    /// Produce the edge map of the image.
+    pub(crate) fn detect(&self, img: &GrayImage) -> GrayImage {
+        match *self {
+            EdgeDetector::Sobel => {
+                let (width, height) = img.dimensions();
+                let (mags, _) = sobel(img);
+                let max = mags.iter().cloned().fold(0., f32::max);
+                let scale = if max > 0. { 255. / max } else { 0. };
+
+                GrayImage::from_vec(width, height, mags.iter().map(|&m| (m * scale) as u8).collect())
+                    .expect("wrong buffer size")
+            },
+            EdgeDetector::Canny { sigma, low, high } => canny(img, sigma, low, high),
+        }
+    }
+}
+
+/// Calculate the magnitude and direction (as one of four sectors, 0 = horizontal gradient
+/// going clockwise by 45 degrees) of the Sobel gradient of each pixel, clamping at the edges.
+fn sobel(img: &GrayImage) -> (Vec<f32>, Vec<u8>) {
+    let (width, height) = img.dimensions();
+    let px = |x: i64, y: i64| {
+        let x = x.max(0).min(width as i64 - 1) as u32;
+        let y = y.max(0).min(height as i64 - 1) as u32;
+        img.get_pixel(x, y)[0] as f32
+    };
+
+    let len = (width * height) as usize;
+    let (mut mags, mut dirs) = (Vec::with_capacity(len), Vec::with_capacity(len));
+
+    for y in 0 .. height as i64 {
+        for x in 0 .. width as i64 {
+            let gx = px(x + 1, y - 1) + 2. * px(x + 1, y) + px(x + 1, y + 1)
+                - px(x - 1, y - 1) - 2. * px(x - 1, y) - px(x - 1, y + 1);
+            let gy = px(x - 1, y + 1) + 2. * px(x, y + 1) + px(x + 1, y + 1)
+                - px(x - 1, y - 1) - 2. * px(x, y - 1) - px(x + 1, y - 1);
+
+            mags.push((gx * gx + gy * gy).sqrt());
+
+            // the angle in `[0, 180)` degrees, rounded to the nearest multiple of 45
+            let angle = gy.atan2(gx).to_degrees().rem_euclid(180.);
+            dirs.push(((angle + 22.5) / 45.) as u8 % 4);
+        }
+    }
+
+    (mags, dirs)
+}
+
+fn canny(img: &GrayImage, sigma: f32, low: f32, high: f32) -> GrayImage {
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    let (mags, dirs) = sobel(&imageops::blur(img, sigma));
+
+    // non-maximum suppression along the gradient direction
+    let mag_at = |x: usize, y: usize, dx: isize, dy: isize| {
+        let (nx, ny) = (x as isize + dx, y as isize + dy);
+        if nx < 0 || ny < 0 || nx >= w as isize || ny >= h as isize {
+            0.
+        } else {
+            mags[ny as usize * w + nx as usize]
+        }
+    };
+
+    let thinned: Vec<f32> = (0 .. w * h).map(|i| {
+        let (x, y) = (i % w, i / w);
+        let (dx, dy) = match dirs[i] {
+            0 => (1, 0),
+            1 => (1, 1),
+            2 => (0, 1),
+            _ => (-1, 1),
+        };
+
+        let mag = mags[i];
+        if mag >= mag_at(x, y, dx, dy) && mag >= mag_at(x, y, -dx, -dy) { mag } else { 0. }
+    }).collect();
+
+    let max = thinned.iter().cloned().fold(0., f32::max);
+    let mut edges = GrayImage::new(width, height);
+
+    if max == 0. {
+        return edges;
+    }
+
+    let (low, high) = (low * max, high * max);
+
+    // hysteresis: trace weak edges from each strong edge
+    let mut stack: Vec<usize> = (0 .. w * h).filter(|&i| thinned[i] >= high).collect();
+    let mut is_edge = vec![false; w * h];
+    stack.iter().for_each(|&i| is_edge[i] = true);
+
+    while let Some(i) = stack.pop() {
+        let (x, y) = ((i % w) as isize, (i / w) as isize);
+
+        for (dx, dy) in (-1 ..= 1).flat_map(|dy| (-1 ..= 1).map(move |dx| (dx, dy))) {
+            let (nx, ny) = (x + dx, y + dy);
+
+            if nx >= 0 && ny >= 0 && (nx as usize) < w && (ny as usize) < h {
+                let n = ny as usize * w + nx as usize;
+
+                if !is_edge[n] && thinned[n] >= low {
+                    is_edge[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    for (px, &edge) in edges.iter_mut().zip(&is_edge) {
+        *px = if edge { 255 } else { 0 };
+    }
+
+    edges
+}
+
+#[test]
+fn test_edge_maps() {
+    use image::Luma;
+
+    let square = GrayImage::from_fn(32, 32, |x, y| {
+        Luma([if (8 .. 24).contains(&x) && (8 .. 24).contains(&y) { 200 } else { 40 }])
+    });
+    // the edges of the inverted image are in the same places
+    let inverted = GrayImage::from_fn(32, 32, |x, y| Luma([255 - square.get_pixel(x, y)[0]]));
+
+    let sobel = EdgeDetector::Sobel.detect(&square);
+    assert_eq!(sobel, EdgeDetector::Sobel.detect(&inverted));
+    assert_eq!(sobel.get_pixel(0, 0)[0], 0);
+    assert_eq!(sobel.get_pixel(16, 16)[0], 0);
+    assert!(sobel.get_pixel(8, 16)[0] > 128);
+
+    let canny = EdgeDetector::canny().detect(&square);
+    assert_eq!(canny, EdgeDetector::canny().detect(&inverted));
+    // a thin outline of the square
+    let edge_pixels = canny.iter().filter(|&&px| px == 255).count();
+    assert!((48 .. 160).contains(&edge_pixels), "{} edge pixels", edge_pixels);
+    assert_eq!(canny.get_pixel(16, 16)[0], 0);
+}
@@ -27,6 +27,8 @@
 #![cfg_attr(feature = "nightly", feature(specialization))]
 
 extern crate base64;
+#[cfg(feature = "exif")]
+extern crate exif;
 
 #[macro_use]
 extern crate serde;
@@ -38,23 +40,45 @@ extern crate transpose;
 
 use serde::{Serialize, Deserialize};
 
-use image::{GrayImage};
+use image::{AnimationDecoder, GrayImage, ImageResult, RgbaImage};
+#[cfg(feature = "exif")]
+use image::io::Reader as ImageReader;
 use image::imageops;
 
 pub use image::imageops::FilterType;
 
 use std::borrow::Cow;
 use std::fmt;
+#[cfg(feature = "exif")]
+use std::fs::File;
+#[cfg(feature = "exif")]
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
 use std::marker::PhantomData;
+#[cfg(feature = "exif")]
+use std::path::Path;
+use std::time::Duration;
 
 mod dct;
+mod dwt;
+mod edges;
+mod normalize;
+#[cfg(feature = "exif")]
+mod orientation;
 
 use dct::DctCtxt;
+use dwt::DwtCtxt;
+
+pub use edges::EdgeDetector;
+pub use normalize::Normalization;
 
 mod alg;
 mod traits;
 
-pub use alg::HashAlg;
+use alg::{alpha, dihedral, pyramid, segment, sequence, tile, trim, MhKernel, SimHashPlanes};
+
+pub use alg::{ColorSpace, DihedralHashes, Grayscale, HashAlg, Keyframe, MultiHash, PdqHash,
+              PdqDihedralHashes, PyramidHash, RadialHash, RadialParams, SegmentParams,
+              SequenceHash, TileHash, TileHashes, TrimParams};
 
 pub use traits::{HashBytes, Image, DiffImage};
 pub(crate) use traits::BitSet;
@@ -98,6 +122,13 @@ pub(crate) use traits::BitSet;
 /// enum. Each algorithm is different but they all produce the same size hashes as governed by
 /// `hash_size`.
 ///
+/// ### Color Space
+/// Setter: [`.color_space()`](#method.color_space)
+/// Definition: [`ColorSpace`](enum.ColorSpace.html)
+///
+/// By default images are converted to grayscale before hashing. Selecting a color space instead
+/// hashes each of its channels separately, producing a hash that is that many times larger.
+///
 /// ### Hash Bytes Container / `B` Type Param
 /// Use [`with_bytes_type::<B>()`](#method.with_bytes_type) instead of `new()` to customize.
 ///
@@ -128,9 +159,43 @@ pub struct HasherConfig<B = Box<[u8]>> {
     resize_filter: FilterType,
     dct: bool,
     hash_alg: HashAlg,
+    #[serde(default = "default_dwt_levels")]
+    dwt_levels: u32,
+    #[serde(default)]
+    dwt_keep_ll: bool,
+    #[serde(default = "default_mh_params")]
+    mh_params: [f32; 2],
+    #[serde(default)]
+    color_space: ColorSpace,
+    #[serde(default)]
+    grayscale: Grayscale,
+    #[serde(default)]
+    background: Option<[u8; 3]>,
+    #[serde(default)]
+    ignore_exif_orientation: bool,
+    #[serde(default)]
+    dihedral: bool,
+    #[serde(default)]
+    segment_params: SegmentParams,
+    #[serde(default)]
+    edge_detector: Option<EdgeDetector>,
+    #[serde(default)]
+    normalization: Option<Normalization>,
+    #[serde(default)]
+    inversion_invariant: bool,
+    #[serde(default)]
+    trim: Option<TrimParams>,
+    #[serde(default = "default_simhash_bits")]
+    simhash_bits: u32,
+    #[serde(default)]
+    simhash_seed: u64,
     _bytes_type: PhantomData<B>,
 }
 
+fn default_dwt_levels() -> u32 { 3 }
+fn default_mh_params() -> [f32; 2] { [2.0, 1.0] }
+fn default_simhash_bits() -> u32 { 64 }
+
 impl HasherConfig<Box<[u8]>> {
     /// Construct a new hasher config with sane, reasonably fast defaults.
     ///
@@ -155,23 +220,55 @@ impl HasherConfig<Box<[u8]>> {
             resize_filter: FilterType::Lanczos3,
             dct: false,
             hash_alg: HashAlg::Gradient,
+            dwt_levels: default_dwt_levels(),
+            dwt_keep_ll: false,
+            mh_params: default_mh_params(),
+            color_space: ColorSpace::Luma,
+            grayscale: Grayscale::Image,
+            background: None,
+            ignore_exif_orientation: false,
+            dihedral: false,
+            segment_params: SegmentParams::default(),
+            edge_detector: None,
+            normalization: None,
+            inversion_invariant: false,
+            trim: None,
+            simhash_bits: default_simhash_bits(),
+            simhash_seed: 0,
             _bytes_type: PhantomData,
         }
     }
 }
 
+impl Default for HasherConfig<Box<[u8]>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<B: HashBytes> HasherConfig<B> {
     /// Set a new hash width and height; these can be the same.
     ///
-    /// The number of bits in the resulting hash will be `width * height`. If you are using
+    /// The number of bits in the resulting hash will be `width * height`, except with
+    /// [`SimHash`](enum.HashAlg.html#variant.SimHash). If you are using
     /// a fixed-size `HashBytes` type then you must ensure it can hold at least this many bits.
     /// You can check this with [`HashBytes::max_bits()`](#method.max_bits).
     ///
     /// ### Rounding Behavior
     /// Certain hash algorithms need to round this value to function properly:
     ///
-    /// * [`DoubleGradient`](enum.HashAlg.html#variant.DoubleGradient) rounds to the next multiple of 2;
-    /// * [`Blockhash`](enum.HashAlg.html#variant.Blockhash) rounds to the next multiple of 4.
+    /// * [`DoubleGradient`](enum.HashAlg.html#variant.DoubleGradient) and
+    ///   [`QuadGradient`](enum.HashAlg.html#variant.QuadGradient) round to the next multiple of 2;
+    /// * [`Blockhash`](enum.HashAlg.html#variant.Blockhash) and
+    ///   [`BlockhashQuick`](enum.HashAlg.html#variant.BlockhashQuick) round to the next multiple of 4;
+    /// * [`BlockMean`](enum.HashAlg.html#variant.BlockMean) always uses `16, 16`;
+    /// * [`BlockMeanOverlap`](enum.HashAlg.html#variant.BlockMeanOverlap) always uses `31, 31`;
+    /// * [`MarrHildreth`](enum.HashAlg.html#variant.MarrHildreth) always uses `24, 24`;
+    /// * [`Pdq`](enum.HashAlg.html#variant.Pdq) always uses `16, 16`.
+    ///
+    /// With [`SimHash`](enum.HashAlg.html#variant.SimHash) this sets the size the image is scaled
+    /// down to instead; the number of bits is set with
+    /// [`simhash_params()`](#method.simhash_params).
     ///
     /// If the chosen values already satisfy these requirements then nothing is changed.
     ///
@@ -203,7 +300,10 @@ impl<B: HashBytes> HasherConfig<B> {
     /// Enable preprocessing with the Discrete Cosine Transform (DCT).
     ///
     /// Does nothing when used with [the Blockhash.io algorithm](HashAlg::Blockhash)
-    /// which does not scale the image.
+    /// which does not scale the image, with [the DCT algorithm](HashAlg::Dct) which always
+    /// performs its own DCT, or with [the Wavelet](HashAlg::Wavelet),
+    /// [Block Mean Value](HashAlg::BlockMean), [Marr-Hildreth](HashAlg::MarrHildreth) and
+    /// [PDQ](HashAlg::Pdq) algorithms.
     /// (RFC: it would be possible to shoehorn a DCT into the Blockhash algorithm but it's
     /// not clear what benefits, if any, that would provide).
     ///
@@ -226,9 +326,10 @@ impl<B: HashBytes> HasherConfig<B> {
     ///
     /// Further Reading:
     /// * http://www.hackerfactor.com/blog/?/archives/432-Looks-Like-It.html
-    /// Krawetz describes a "pHash" algorithm which is equivalent to Mean + DCT preprocessing here.
-    /// However there is nothing to say that DCT preprocessing cannot compose with other hash
-    /// algorithms; Gradient + DCT might well perform better in some aspects.
+    ///   Krawetz describes a "pHash" algorithm which is similar to Mean + DCT preprocessing here
+    ///   (see [`HashAlg::Dct`](enum.HashAlg.html#variant.Dct) for the canonical version).
+    ///   However there is nothing to say that DCT preprocessing cannot compose with other hash
+    ///   algorithms; Gradient + DCT might well perform better in some aspects.
     /// * https://en.wikipedia.org/wiki/Discrete_cosine_transform
     pub fn preproc_dct(self) -> Self {
         Self { dct: true, ..self }
@@ -257,26 +358,240 @@ impl<B: HashBytes> HasherConfig<B> {
     /// Further reading:
     /// * https://en.wikipedia.org/wiki/Difference_of_Gaussians
     /// * http://homepages.inf.ed.ac.uk/rbf/HIPR2/log.htm
-    /// (Difference of Gaussians is an approximation of a Laplacian of Gaussian filter)
+    ///   (Difference of Gaussians is an approximation of a Laplacian of Gaussian filter)
     pub fn preproc_diff_gauss_sigmas(self, sigma_a: f32, sigma_b: f32) -> Self {
         Self { gauss_sigmas: Some([sigma_a, sigma_b]), ..self }
     }
 
+    /// Enable preprocessing with the given edge detector.
+    ///
+    /// After conversion to grayscale (and Difference of Gaussians preprocessing, if enabled), the
+    /// image is replaced by a map of its edges which the configured hash algorithm is performed
+    /// on. This applies to every algorithm, including those that don't otherwise convert the
+    /// image to grayscale.
+    ///
+    /// Edges are mostly unaffected by changes in color and luminance, so this helps with
+    /// cartoons, diagrams and logos that have been recolored. Unlike
+    /// [Difference of Gaussians](#method.preproc_diff_gauss_sigmas), which keeps a band of
+    /// frequencies of the image, the edge map is independent of the contrast of the edges
+    /// (apart from which edges are kept, for Canny).
+    ///
+    /// See [`EdgeDetector`](enum.EdgeDetector.html) for the available detectors.
+    pub fn preproc_edges(self, edge_detector: EdgeDetector) -> Self {
+        Self { edge_detector: Some(edge_detector), ..self }
+    }
+
+    /// Enable contrast normalization of the grayscale image with the given method.
+    ///
+    /// After conversion to grayscale (and Difference of Gaussians preprocessing, if enabled), the
+    /// luminance values of the image are normalized before it is resized or passed to
+    /// [edge detection](#method.preproc_edges). Like edge detection, this applies to every
+    /// algorithm, including those that don't otherwise convert the image to grayscale.
+    ///
+    /// Brightness and contrast edits move luminance values across the thresholds that decide
+    /// marginal bits of a hash, which normalization mostly undoes.
+    ///
+    /// See [`Normalization`](enum.Normalization.html) for the available methods.
+    pub fn preproc_normalize(self, normalization: Normalization) -> Self {
+        Self { normalization: Some(normalization), ..self }
+    }
+
+    /// Set the parameters of the Discrete Wavelet Transform performed by
+    /// [the Wavelet algorithm](enum.HashAlg.html#variant.Wavelet).
+    ///
+    /// The image is scaled down to `(width * 2^levels) x (height * 2^levels)` before the
+    /// transform, so each added level doubles the resolution that the hash is computed from.
+    /// The default is 3 levels, i.e. 64 x 64 for an 8 x 8 hash.
+    ///
+    /// If `remove_ll` is `true` (the default), the image is first fully decomposed and its
+    /// lowest-frequency (LL) band is zeroed, as is done by the `whash` implementation in Python's
+    /// `imagehash`.
+    ///
+    /// Has no effect with other algorithms.
+    pub fn wavelet_params(self, levels: u32, remove_ll: bool) -> Self {
+        Self { dwt_levels: levels, dwt_keep_ll: !remove_ll, ..self }
+    }
+
+    /// Set the parameters of the kernel used by
+    /// [the Marr-Hildreth algorithm](enum.HashAlg.html#variant.MarrHildreth).
+    ///
+    /// The kernel has a radius of `4 * alpha^level` pixels and is scaled by `alpha^-level`, so
+    /// larger values detect coarser edges (and take longer). The defaults are `2.0, 1.0`, the same
+    /// as pHash.
+    ///
+    /// `alpha` must be positive and the radius must be between 1 and 128 pixels, otherwise
+    /// [`to_hasher()`](#method.to_hasher) panics.
+    ///
+    /// Has no effect with other algorithms.
+    pub fn marr_hildreth_params(self, alpha: f32, level: f32) -> Self {
+        Self { mh_params: [alpha, level], ..self }
+    }
+
+    /// Set the color space that images are hashed in.
+    ///
+    /// With any color space besides [`ColorSpace::Luma`](enum.ColorSpace.html#variant.Luma)
+    /// (the default), the configured algorithm is run separately on each channel of the color
+    /// space and the hashes are concatenated, so the hash is three times larger. This can tell
+    /// apart images that only differ in hue or by swapped channels, which have very similar
+    /// grayscale hashes.
+    ///
+    /// The distance for each channel can be found with
+    /// [`Hasher::channel_dists()`](struct.Hasher.html#method.channel_dists).
+    pub fn color_space(self, color_space: ColorSpace) -> Self {
+        Self { color_space, ..self }
+    }
+
+    /// Set how color images are converted to grayscale.
+    ///
+    /// The default, [`Grayscale::Image`](enum.Grayscale.html#variant.Image), is the conversion
+    /// provided by the `image` crate. Hashes from other libraries can be reproduced by picking the
+    /// same conversion they use, and
+    /// [`Grayscale::LinearLight`](enum.Grayscale.html#variant.LinearLight) makes hashes more stable
+    /// under gamma adjustments.
+    ///
+    /// Has no effect on [Blockhash](enum.HashAlg.html#variant.Blockhash),
+    /// [Block Mean Value](enum.HashAlg.html#variant.BlockMean) or
+    /// [PDQ](enum.HashAlg.html#variant.Pdq), which convert the image themselves, unless
+    /// [normalization](#method.preproc_normalize) or [edge detection](#method.preproc_edges) is
+    /// enabled, nor with color spaces besides `Luma`.
+    pub fn grayscale(self, grayscale: Grayscale) -> Self {
+        Self { grayscale, ..self }
+    }
+
+    /// Composite images with an alpha channel over an opaque background of the given RGB color
+    /// before hashing them.
+    ///
+    /// Otherwise, algorithms handle transparency differently: most drop the alpha channel so
+    /// transparent areas hash as whatever color happens to be stored in them, while
+    /// [Blockhash](enum.HashAlg.html#variant.Blockhash) treats fully transparent pixels as white
+    /// and ignores partial transparency. With this set, a transparent image hashes the same as
+    /// the opaque image it displays as over the background, with every algorithm.
+    ///
+    /// Images that are already opaque are hashed unchanged.
+    pub fn composite_alpha(self, background: [u8; 3]) -> Self {
+        Self { background: Some(background), ..self }
+    }
+
+    /// Set the number of bits and the seed of the random hyperplanes of
+    /// [the SimHash algorithm](enum.HashAlg.html#variant.SimHash).
+    ///
+    /// The same seed always generates the same hyperplanes, so hashes are reproducible. The
+    /// defaults are 64 bits with a seed of 0.
+    ///
+    /// Has no effect with other algorithms.
+    pub fn simhash_params(self, bits: u32, seed: u64) -> Self {
+        Self { simhash_bits: bits, simhash_seed: seed, ..self }
+    }
+
+    /// Make hashes invariant to rotating the image by multiples of 90 degrees and to mirroring it.
+    ///
+    /// [`Hasher::hash_image()`](struct.Hasher.html#method.hash_image) will hash all eight
+    /// rotations and reflections of the image and return the
+    /// [canonical hash](struct.DihedralHashes.html#method.canonical) of them. Use
+    /// [`Hasher::hash_image_dihedral()`](struct.Hasher.html#method.hash_image_dihedral) instead
+    /// to get all eight hashes.
+    pub fn dihedral_invariant(self) -> Self {
+        Self { dihedral: true, ..self }
+    }
+
+    /// Trim uniform borders, such as letterbox bars or padding, from images before hashing them.
+    ///
+    /// Each side of the image is trimmed for as long as its rows or columns are uniformly one
+    /// color (within the tolerance in `params`), as long as enough of the image remains. Use
+    /// [`Hasher::hash_image_with_bounds()`](struct.Hasher.html#method.hash_image_with_bounds)
+    /// to find out what was trimmed.
+    ///
+    /// Borders shift the content of the image within the resized grid that most algorithms hash,
+    /// so images with added borders otherwise have very different hashes from the originals.
+    ///
+    /// This applies to [`Hasher::hash_image()`](struct.Hasher.html#method.hash_image),
+    /// [`hash_image_dihedral()`](struct.Hasher.html#method.hash_image_dihedral) and each frame
+    /// hashed by [`hash_frames()`](struct.Hasher.html#method.hash_frames), but not to the parts of
+    /// images hashed by the segment, tile and pyramid methods.
+    pub fn trim_borders(self, params: TrimParams) -> Self {
+        Self { trim: Some(params), ..self }
+    }
+
+    /// Make hashes invariant to inverting the luminance of the image (i.e. taking its negative).
+    ///
+    /// For most algorithms, the hash of an inverted image is nearly the complement of the hash of
+    /// the original. With this set, the hash (or the hash of each channel, with a
+    /// [color space](#method.color_space)) is complemented if its first bit is set, so that
+    /// an image and its negative get the same hash.
+    ///
+    /// If the first bit itself is unstable, similar images may end up with complementary
+    /// hashes. Comparing with
+    /// [`ImageHash::inversion_invariant_dist()`](struct.ImageHash.html#method.inversion_invariant_dist)
+    /// avoids that, and works with or without this option.
+    pub fn inversion_invariant(self) -> Self {
+        Self { inversion_invariant: true, ..self }
+    }
+
+    /// Don't apply the EXIF orientation of images decoded by
+    /// [`Hasher::hash_path()`](struct.Hasher.html#method.hash_path) and
+    /// [`hash_reader()`](struct.Hasher.html#method.hash_reader); hash the pixels as they're stored.
+    ///
+    /// Has no effect without the `exif` feature, which provides those methods.
+    pub fn ignore_exif_orientation(self) -> Self {
+        Self { ignore_exif_orientation: true, ..self }
+    }
+
+    /// Set the parameters used to segment images for
+    /// [`Hasher::hash_image_segments()`](struct.Hasher.html#method.hash_image_segments).
+    pub fn segment_params(self, segment_params: SegmentParams) -> Self {
+        Self { segment_params, ..self }
+    }
+
     /// Create a [`Hasher`](struct.Hasher.html) from this config which can be used to hash images.
     ///
     /// ### Panics
-    /// If the chosen hash size (`width x height`, rounded for the algorithm if necessary)
-    /// is too large for the chosen container type (`B::max_bits()`).
+    /// If the chosen hash size (`width x height`, rounded for the algorithm if necessary
+    /// and multiplied by the number of channels of the color space) is too large for the chosen
+    /// container type (`B::max_bits()`), if either dimension of the hash size is zero, or if
+    /// [the SimHash algorithm](enum.HashAlg.html#variant.SimHash) is chosen with zero bits.
+    /// Also if [the Marr-Hildreth algorithm](enum.HashAlg.html#variant.MarrHildreth) is chosen
+    /// with [parameters](#method.marr_hildreth_params) outside of the allowed range.
     pub fn to_hasher(&self) -> Hasher<B> {
-        let Self { hash_alg, width, height, gauss_sigmas, resize_filter, dct, .. } = *self;
+        let Self {
+            hash_alg, width, height, gauss_sigmas, resize_filter, dct, dwt_levels, dwt_keep_ll,
+            mh_params: [mh_alpha, mh_level], color_space, grayscale, background, dihedral,
+            segment_params, edge_detector, normalization, inversion_invariant, trim, simhash_bits,
+            simhash_seed, ignore_exif_orientation, ..
+        } = *self;
+
+        assert!(width > 0 && height > 0, "hash size must be nonzero, got {} x {}", width, height);
+        assert!(simhash_bits > 0 || hash_alg != HashAlg::SimHash,
+                "SimHash needs at least one bit, see `simhash_params()`");
 
         let (width, height) = hash_alg.round_hash_size(width, height);
 
-        assert!((width * height) as usize <= B::max_bits(),
-                "hash size too large for container: {} x {}", width, height);
+        let simhash_planes = if hash_alg == HashAlg::SimHash {
+            let (resize_width, resize_height) = hash_alg.resize_dimensions(width, height);
+            let dims = (resize_width * resize_height) as usize;
+            Some(SimHashPlanes::new(simhash_bits, dims, simhash_seed))
+        } else {
+            None
+        };
+
+        let channel_bits = simhash_planes.as_ref()
+            .map_or((width * height) as usize, SimHashPlanes::bits);
 
-        // Blockhash doesn't resize the image so don't waste time calculating coefficients
-        let dct_coeffs = if dct && hash_alg != HashAlg::Blockhash {
+        let hash_bits = if color_space == ColorSpace::Luma {
+            channel_bits
+        } else {
+            // each channel is padded to a whole number of bytes
+            (channel_bits + 7) / 8 * 8 * color_space.channels()
+        };
+
+        assert!(hash_bits <= B::max_bits(),
+                "hash size too large for container: {} bits ({:?})", hash_bits, color_space);
+
+        // some algorithms don't resize the image so don't waste time calculating coefficients
+        let dct_coeffs = if hash_alg == HashAlg::Dct {
+            // pHash proper always needs a DCT and it's sized exactly to the resized image
+            let (dct_width, dct_height) = hash_alg.resize_dimensions(width, height);
+            Some(DctCtxt::exact(dct_width, dct_height))
+        } else if dct && hash_alg.supports_preproc_dct() {
             // calculate the coefficients based on the resize dimensions
             let (dct_width, dct_height) = hash_alg.resize_dimensions(width, height);
             Some(DctCtxt::new(dct_width, dct_height))
@@ -284,12 +599,32 @@ impl<B: HashBytes> HasherConfig<B> {
             None
         };
 
+        let dwt_ctxt = if hash_alg == HashAlg::Wavelet {
+            Some(DwtCtxt::new(width, height, dwt_levels, !dwt_keep_ll))
+        } else {
+            None
+        };
+
+        let mh_kernel = if hash_alg == HashAlg::MarrHildreth {
+            Some(MhKernel::new(mh_alpha, mh_level))
+        } else {
+            None
+        };
+
         Hasher {
             ctxt: HashCtxt {
                 gauss_sigmas,
-                dct_ctxt: dct_coeffs, width, height, resize_filter,
+                dct_ctxt: dct_coeffs, dwt_ctxt, mh_kernel, simhash_planes, edge_detector,
+                normalization, grayscale, width, height, resize_filter,
             },
             hash_alg,
+            color_space,
+            dihedral,
+            inversion_invariant,
+            segment_params,
+            trim,
+            background,
+            ignore_exif_orientation,
             bytes_type: PhantomData
         }
 
@@ -306,6 +641,21 @@ impl<B> fmt::Debug for HasherConfig<B> {
             .field("resize_filter", &debug_filter_type(&self.resize_filter))
             .field("gauss_sigmas", &self.gauss_sigmas)
             .field("use_dct", &self.dct)
+            .field("dwt_levels", &self.dwt_levels)
+            .field("dwt_keep_ll", &self.dwt_keep_ll)
+            .field("mh_params", &self.mh_params)
+            .field("color_space", &self.color_space)
+            .field("grayscale", &self.grayscale)
+            .field("background", &self.background)
+            .field("ignore_exif_orientation", &self.ignore_exif_orientation)
+            .field("dihedral", &self.dihedral)
+            .field("segment_params", &self.segment_params)
+            .field("edge_detector", &self.edge_detector)
+            .field("normalization", &self.normalization)
+            .field("inversion_invariant", &self.inversion_invariant)
+            .field("trim", &self.trim)
+            .field("simhash_bits", &self.simhash_bits)
+            .field("simhash_seed", &self.simhash_seed)
             .finish()
     }
 }
@@ -316,14 +666,372 @@ impl<B> fmt::Debug for HasherConfig<B> {
 pub struct Hasher<B = Box<[u8]>> {
     ctxt: HashCtxt,
     hash_alg: HashAlg,
+    color_space: ColorSpace,
+    dihedral: bool,
+    inversion_invariant: bool,
+    segment_params: SegmentParams,
+    trim: Option<TrimParams>,
+    background: Option<[u8; 3]>,
+    #[cfg_attr(not(feature = "exif"), allow(dead_code))]
+    ignore_exif_orientation: bool,
     bytes_type: PhantomData<B>,
 }
 
 impl<B> Hasher<B> where B: HashBytes {
     /// Calculate a hash for the given image with the configured options.
     pub fn hash_image<I: Image>(&self, img: &I) -> ImageHash<B> {
-        let hash = self.hash_alg.hash_image(&self.ctxt, img);
-        ImageHash { hash, __backcompat: () }
+        self.hash_image_with_bounds(img).0
+    }
+
+    /// Calculate a hash for the given image with the configured options, and return the bounds
+    /// of the part of the image that was hashed as `[x, y, width, height]`.
+    ///
+    /// The bounds are those of the whole image unless
+    /// [`HasherConfig::trim_borders()`](struct.HasherConfig.html#method.trim_borders) is set
+    /// and borders were found.
+    pub fn hash_image_with_bounds<I: Image>(&self, img: &I) -> (ImageHash<B>, [u32; 4]) {
+        if let Some(opaque) = self.composite_alpha(img) {
+            return self.hash_image_with_bounds(&opaque);
+        }
+
+        match self.trim_borders(img) {
+            Some((trimmed, bounds)) => (self.hash_untrimmed(&trimmed), bounds),
+            None => {
+                let (width, height) = img.dimensions();
+                (self.hash_untrimmed(img), [0, 0, width, height])
+            },
+        }
+    }
+
+    fn hash_untrimmed<I: Image>(&self, img: &I) -> ImageHash<B> {
+        if self.dihedral {
+            return self.hash_untrimmed_dihedral(img).into_canonical();
+        }
+
+        ImageHash { hash: self.hash_untransformed(img), __backcompat: () }
+    }
+
+    /// If alpha compositing is configured and the image has transparent pixels, composite it
+    /// over the background.
+    fn composite_alpha<I: Image>(&self, img: &I) -> Option<RgbaImage> {
+        alpha::composite_over(img, self.background?)
+    }
+
+    /// If border trimming is configured and the image has borders, crop them off.
+    fn trim_borders<I: Image>(&self, img: &I) -> Option<(RgbaImage, [u32; 4])> {
+        let params = self.trim.as_ref()?;
+
+        let rgba = dihedral::to_rgba(img);
+        let bounds = trim::content_bounds(&rgba, params);
+        let [x, y, width, height] = bounds;
+
+        if (width, height) == rgba.dimensions() {
+            return None;
+        }
+
+        Some((imageops::crop_imm(&rgba, x, y, width, height).to_image(), bounds))
+    }
+
+    /// Calculate the hashes of all eight rotations and reflections of the given image.
+    ///
+    /// For the algorithms which only compare the values of the resized image (Mean, Median,
+    /// the gradient algorithms and Block Mean Value) without DCT preprocessing or a color space,
+    /// the resized image is transformed instead of the original, so this costs little more
+    /// than a single hash. The results may differ by a few bits from hashing transformed copies
+    /// of the image.
+    ///
+    /// This ignores
+    /// [`HasherConfig::dihedral_invariant()`](struct.HasherConfig.html#method.dihedral_invariant).
+    pub fn hash_image_dihedral<I: Image>(&self, img: &I) -> DihedralHashes<B> {
+        if let Some(opaque) = self.composite_alpha(img) {
+            return self.hash_image_dihedral(&opaque);
+        }
+
+        match self.trim_borders(img) {
+            Some((trimmed, _)) => self.hash_untrimmed_dihedral(&trimmed),
+            None => self.hash_untrimmed_dihedral(img),
+        }
+    }
+
+    fn hash_untrimmed_dihedral<I: Image>(&self, img: &I) -> DihedralHashes<B> {
+        if self.hash_alg.hashes_resized() && self.ctxt.dct_ctxt.is_none()
+            && self.color_space == ColorSpace::Luma {
+            let hashes = dihedral::hash_resized(self.hash_alg, &self.ctxt, img);
+            let hashes = hashes.map(|hash| self.canonicalize_inversion(hash));
+            return DihedralHashes::from_hashes(hashes);
+        }
+
+        let rgba = dihedral::to_rgba(img);
+
+        DihedralHashes::from_hashes((0 .. 8).map(|n| {
+            if n == 0 {
+                self.hash_untransformed(img)
+            } else {
+                self.hash_untransformed(&dihedral::transform(&rgba, n))
+            }
+        }))
+    }
+
+    /// Calculate a crop-resistant hash of the given image by hashing each of its large bright and
+    /// dark regions separately.
+    ///
+    /// The image is converted to grayscale, scaled down and blurred, and then split into
+    /// connected regions of pixels that are all brighter or all darker than a threshold, as
+    /// configured with
+    /// [`HasherConfig::segment_params()`](struct.HasherConfig.html#method.segment_params).
+    /// The bounding box of each region that is large enough is then cropped from the original
+    /// image and hashed with the configured options.
+    ///
+    /// As long as some of the regions of an image survive cropping, the
+    /// [`MultiHash`](struct.MultiHash.html) of the cropped image will have matches with that of
+    /// the original.
+    ///
+    /// Further Reading:
+    /// https://github.com/JohannesBuchner/imagehash
+    pub fn hash_image_segments<I: Image>(&self, img: &I) -> MultiHash<B> {
+        if let Some(opaque) = self.composite_alpha(img) {
+            return self.hash_image_segments(&opaque);
+        }
+
+        let grayscale = self.ctxt.grayscale.convert(img);
+        let bounds = segment::segment_bounds(&grayscale, &self.segment_params,
+                                             self.ctxt.resize_filter);
+
+        let rgba = dihedral::to_rgba(img);
+
+        MultiHash::from_hashes(bounds.into_iter().map(|[x, y, width, height]| {
+            self.hash_untrimmed(&imageops::crop_imm(&rgba, x, y, width, height).to_image())
+        }).collect())
+    }
+
+    /// Split the given image into a grid of `columns x rows` tiles and hash each tile with the
+    /// configured options.
+    ///
+    /// Adjacent tiles share `overlap` of their width or height, from `0.0` (no overlap) up to
+    /// but not including `1.0`. Overlapping tiles make it more likely that some tile lines up
+    /// with an embedded image.
+    ///
+    /// The tiles of two images can be compared with
+    /// [`TileHashes::matching_tiles()`](struct.TileHashes.html#method.matching_tiles).
+    ///
+    /// ### Panics
+    /// If `columns` or `rows` is 0 or `overlap` is not in `[0, 1)`.
+    pub fn hash_image_tiles<I: Image>(&self, img: &I, columns: u32, rows: u32, overlap: f32)
+            -> TileHashes<B> {
+        if let Some(opaque) = self.composite_alpha(img) {
+            return self.hash_image_tiles(&opaque, columns, rows, overlap);
+        }
+
+        let (width, height) = img.dimensions();
+        let bounds = tile::tile_bounds(width, height, columns, rows, overlap);
+
+        let rgba = dihedral::to_rgba(img);
+
+        TileHashes::from_tiles(bounds.into_iter().enumerate().map(|(i, bounds)| {
+            let [x, y, width, height] = bounds;
+
+            TileHash {
+                column: i as u32 % columns,
+                row: i as u32 / columns,
+                bounds,
+                hash: self.hash_untrimmed(&imageops::crop_imm(&rgba, x, y, width, height).to_image()),
+            }
+        }).collect())
+    }
+
+    /// Hash the given image at `levels` scales: the whole image, then each of its 2 x 2
+    /// quadrants, then each cell of a 4 x 4 grid, and so on.
+    ///
+    /// The image is scaled once to a square intermediate image with 64 x 64 pixels for each cell
+    /// of the finest level, and the cells of every level are cut from that and hashed with the
+    /// configured options. The number of hashes grows by 4 times with each level, so only a few
+    /// levels are practical.
+    ///
+    /// ### Panics
+    /// If `levels` is 0 or greater than 6.
+    pub fn hash_image_pyramid<I: Image>(&self, img: &I, levels: u32) -> PyramidHash<B> {
+        assert!(levels > 0, "pyramid must have at least one level");
+        assert!(levels <= pyramid::MAX_LEVELS, "pyramid may have at most {} levels, got {}",
+                pyramid::MAX_LEVELS, levels);
+
+        if let Some(opaque) = self.composite_alpha(img) {
+            return self.hash_image_pyramid(&opaque, levels);
+        }
+
+        let side = pyramid::CELL_SIDE << (levels - 1);
+        let intermediate = imageops::resize(&dihedral::to_rgba(img), side, side,
+                                            self.ctxt.resize_filter);
+
+        PyramidHash::from_levels((0 .. levels).map(|level| {
+            let cells = 1 << level;
+
+            let bounds = tile::tile_bounds(side, side, cells, cells, 0.);
+
+            bounds.into_iter().map(|[x, y, width, height]| {
+                self.hash_untrimmed(&imageops::crop_imm(&intermediate, x, y, width, height).to_image())
+            }).collect()
+        }).collect())
+    }
+
+    /// Hash the frames of an animation, given with the time each is shown for.
+    ///
+    /// Consecutive frames whose hashes are within `max_dist` of the first frame of their run
+    /// are merged into a single keyframe, so static stretches of the animation are only stored
+    /// once. With `max_dist` of 0 only frames with identical hashes are merged.
+    ///
+    /// Sequences can be compared with
+    /// [`SequenceHash::dist()`](struct.SequenceHash.html#method.dist).
+    pub fn hash_frames<I, F>(&self, frames: F, max_dist: u32) -> SequenceHash<B>
+    where I: Image, F: IntoIterator<Item = (I, Duration)> {
+        let hashes = frames.into_iter().map(|(frame, delay)| (self.hash_image(&frame), delay));
+        sequence::keyframes(hashes, max_dist)
+    }
+
+    /// Decode and hash the frames of an animation, such as an animated GIF, APNG or WebP.
+    ///
+    /// See [`hash_frames()`](#method.hash_frames) for how frames are merged.
+    ///
+    /// The frames are decoded and hashed one at a time, so only one is held in memory at once.
+    ///
+    /// ### Errors
+    /// Returns the first error from decoding a frame.
+    pub fn hash_animation<'a, D>(&self, decoder: D, max_dist: u32) -> ImageResult<SequenceHash<B>>
+    where D: AnimationDecoder<'a> {
+        let mut error = None;
+
+        // stop at the first error, which is returned instead of the hash
+        let frames = decoder.into_frames().map_while(|frame| match frame {
+            Ok(frame) => Some(frame),
+            Err(e) => {
+                error = Some(e);
+                None
+            },
+        });
+
+        let hash = self.hash_frames(frames.map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = Duration::from_nanos(numer as u64 * 1_000_000 / denom.max(1) as u64);
+            (frame.into_buffer(), delay)
+        }), max_dist);
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(hash),
+        }
+    }
+
+    /// Decode the image file at `path` and hash it with
+    /// [`hash_image()`](#method.hash_image), after rotating or flipping it upright as described by
+    /// its EXIF Orientation tag.
+    ///
+    /// The format is guessed from the contents of the file, and must be enabled in the `image`
+    /// crate's features. Files without EXIF data are hashed as they're stored; the orientation
+    /// can be ignored altogether with
+    /// [`HasherConfig::ignore_exif_orientation()`](struct.HasherConfig.html#method.ignore_exif_orientation).
+    ///
+    /// Requires the `exif` feature, which is enabled by default.
+    ///
+    /// ### Errors
+    /// Returns an error if the file can't be read or decoded.
+    #[cfg(feature = "exif")]
+    pub fn hash_path<P: AsRef<Path>>(&self, path: P) -> ImageResult<ImageHash<B>> {
+        self.hash_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Decode an image file from `reader` and hash it, as with
+    /// [`hash_path()`](#method.hash_path).
+    ///
+    /// Requires the `exif` feature, which is enabled by default.
+    ///
+    /// ### Errors
+    /// Returns an error if reading or decoding fails.
+    #[cfg(feature = "exif")]
+    pub fn hash_reader<R: BufRead + Seek>(&self, mut reader: R) -> ImageResult<ImageHash<B>> {
+        let orientation = if self.ignore_exif_orientation {
+            1
+        } else {
+            let start = reader.stream_position()?;
+            let orientation = orientation::read_orientation(&mut reader);
+            reader.seek(SeekFrom::Start(start))?;
+            orientation
+        };
+
+        let img = ImageReader::new(reader).with_guessed_format()?.decode()?;
+
+        Ok(self.hash_image(&orientation::apply_orientation(img, orientation)))
+    }
+
+    fn hash_untransformed<I: Image>(&self, img: &I) -> B {
+        let hash = if self.color_space == ColorSpace::Luma {
+            self.hash_alg.hash_image(&self.ctxt, img)
+        } else {
+            let channel_bytes = self.ctxt.channel_bytes();
+
+            let channels = self.color_space.split_channels(img);
+
+            B::from_iter(channels.iter().flat_map(|channel| {
+                let mut hash: Vec<u8> = self.hash_alg.hash_image(&self.ctxt, channel);
+                hash.resize(channel_bytes, 0);
+                hash
+            }))
+        };
+
+        self.canonicalize_inversion(hash)
+    }
+
+    /// If inversion invariance is configured, complement each channel of the hash whose first bit
+    /// is set.
+    fn canonicalize_inversion(&self, hash: B) -> B {
+        if !self.inversion_invariant {
+            return hash;
+        }
+
+        let (channel_bits, channel_bytes) = (self.ctxt.channel_bits(), self.ctxt.channel_bytes());
+        let mut bytes = hash.as_slice().to_vec();
+
+        for channel in bytes.chunks_mut(channel_bytes).take(self.color_space.channels()) {
+            if channel[0] & 1 == 0 {
+                continue;
+            }
+
+            for (i, byte) in channel.iter_mut().enumerate() {
+                // leave the padding bits of the last byte zeroed
+                let valid_bits = (channel_bits - i * 8).min(8);
+                *byte ^= (0xFFu16 >> (8 - valid_bits)) as u8;
+            }
+        }
+
+        B::from_iter(bytes.into_iter())
+    }
+
+    /// Get the number of bits in the hashes from this hasher.
+    ///
+    /// With a [color space](struct.HasherConfig.html#method.color_space) this includes the
+    /// padding of each channel to a whole number of bytes.
+    pub fn hash_bits(&self) -> u32 {
+        if self.color_space == ColorSpace::Luma {
+            self.ctxt.channel_bits() as u32
+        } else {
+            (self.ctxt.channel_bytes() * 8 * self.color_space.channels()) as u32
+        }
+    }
+
+    /// Calculate the Hamming distance between each channel of two hashes from this hasher.
+    ///
+    /// The distances are in the order of the channels of the configured
+    /// [`ColorSpace`](enum.ColorSpace.html) and sum to `left.dist(right)`. With
+    /// `ColorSpace::Luma` this is just that distance.
+    pub fn channel_dists(&self, left: &ImageHash<B>, right: &ImageHash<B>) -> Vec<u32> {
+        if self.color_space == ColorSpace::Luma {
+            return vec![left.dist(right)];
+        }
+
+        let channel_bytes = self.ctxt.channel_bytes();
+
+        left.as_bytes().chunks(channel_bytes).zip(right.as_bytes().chunks(channel_bytes))
+            .take(self.color_space.channels())
+            .map(|(l, r)| l.iter().zip(r).map(|(l, r)| (l ^ r).count_ones()).sum())
+            .collect()
     }
 }
 
@@ -333,10 +1041,10 @@ enum CowImage<'a, I: Image> {
 }
 
 impl<'a, I: Image> CowImage<'a, I> {
-    fn to_grayscale(&self) -> Cow<GrayImage> {
+    fn to_grayscale(&self, method: &Grayscale) -> Cow<'_, GrayImage> {
         match *self {
-            CowImage::Borrowed(ref img) => img.to_grayscale(),
-            CowImage::Owned(ref img) => img.to_grayscale(),
+            CowImage::Borrowed(img) => method.convert(img),
+            CowImage::Owned(ref img) => method.convert(img),
         }
     }
 }
@@ -350,12 +1058,29 @@ enum HashVals {
 struct HashCtxt {
     gauss_sigmas: Option<[f32; 2]>,
     dct_ctxt: Option<DctCtxt>,
+    dwt_ctxt: Option<DwtCtxt>,
+    mh_kernel: Option<MhKernel>,
+    simhash_planes: Option<SimHashPlanes>,
+    edge_detector: Option<EdgeDetector>,
+    normalization: Option<Normalization>,
+    grayscale: Grayscale,
     resize_filter: FilterType,
     width: u32,
     height: u32,
 }
 
 impl HashCtxt {
+    /// The number of bytes a hash of a single channel takes.
+    fn channel_bytes(&self) -> usize {
+        (self.channel_bits() + 7) / 8
+    }
+
+    /// The number of bits in a hash of a single channel.
+    fn channel_bits(&self) -> usize {
+        self.simhash_planes.as_ref()
+            .map_or((self.width * self.height) as usize, SimHashPlanes::bits)
+    }
+
     /// If Difference of Gaussians preprocessing is configured, produce a new image with it applied.
     fn gauss_preproc<'a, I: Image>(&self, image: &'a I) -> CowImage<'a, I> {
         if let Some([sigma_a, sigma_b]) = self.gauss_sigmas {
@@ -369,23 +1094,54 @@ impl HashCtxt {
         }
     }
 
-    /// If DCT preprocessing is configured, produce a vector of floats, otherwise a vector of bytes.
-    fn calc_hash_vals(&self, img: &GrayImage, width: u32, height: u32) -> HashVals {
-        if let Some(ref dct_ctxt) = self.dct_ctxt {
-            let img = imageops::resize(img, dct_ctxt.width(), dct_ctxt.height(),
-                                       self.resize_filter);
+    /// Whether any preprocessing of the grayscale image is configured.
+    fn has_gray_preproc(&self) -> bool {
+        self.normalization.is_some() || self.edge_detector.is_some()
+    }
+
+    /// Apply contrast normalization and then edge detection to the grayscale image, if configured.
+    fn gray_preproc<'a>(&self, grayscale: Cow<'a, GrayImage>) -> Cow<'a, GrayImage> {
+        let normalized = match self.normalization {
+            Some(ref normalization) => Cow::Owned(normalization.normalize(&grayscale)),
+            None => grayscale,
+        };
 
-            let img_vals  = img.into_vec();
-            let input_len = img_vals.len() * 2;
+        match self.edge_detector {
+            Some(ref detector) => Cow::Owned(detector.detect(&normalized)),
+            None => normalized,
+        }
+    }
 
-            let mut vals_with_scratch = Vec::with_capacity(input_len);
+    /// Resize the image to the dimensions of `dct_ctxt` and return the uncropped DCT coefficients.
+    fn dct_coeffs(&self, dct_ctxt: &DctCtxt, img: &GrayImage) -> Vec<f32> {
+        let img = imageops::resize(img, dct_ctxt.width(), dct_ctxt.height(),
+                                   self.resize_filter);
 
-            // put the image values in [..width * height] and provide scratch space
-            vals_with_scratch.extend(img_vals.into_iter().map(|x| x as f32));
-            // TODO: compare with `.set_len()`
-            vals_with_scratch.resize(input_len, 0.);
+        let img_vals  = img.into_vec();
+        let input_len = img_vals.len() * 2;
+
+        let mut vals_with_scratch = Vec::with_capacity(input_len);
+
+        // put the image values in [..width * height] and provide scratch space
+        vals_with_scratch.extend(img_vals.into_iter().map(|x| x as f32));
+        // TODO: compare with `.set_len()`
+        vals_with_scratch.resize(input_len, 0.);
+
+        dct_ctxt.dct_2d(vals_with_scratch)
+    }
 
-            let hash_vals = dct_ctxt.dct_2d(vals_with_scratch);
+    /// Resize the image to the dimensions of `dwt_ctxt` and return the LL band of its DWT.
+    fn dwt_ll_band(&self, dwt_ctxt: &DwtCtxt, img: &GrayImage) -> Vec<f32> {
+        let img = imageops::resize(img, dwt_ctxt.width(), dwt_ctxt.height(),
+                                   self.resize_filter);
+
+        dwt_ctxt.ll_band(img.into_vec().into_iter().map(|x| x as f32).collect())
+    }
+
+    /// If DCT preprocessing is configured, produce a vector of floats, otherwise a vector of bytes.
+    fn calc_hash_vals(&self, img: &GrayImage, width: u32, height: u32) -> HashVals {
+        if let Some(ref dct_ctxt) = self.dct_ctxt {
+            let hash_vals = self.dct_coeffs(dct_ctxt, img);
             HashVals::Floats(dct_ctxt.crop_2d(hash_vals))
         } else {
             let img = imageops::resize(img, width, height, self.resize_filter);
@@ -453,6 +1209,19 @@ impl<B: HashBytes> ImageHash<B> {
         BitSet::hamming(&self.hash, &other.hash)
     }
 
+    /// Calculate the Hamming distance between this and `other`, treating each hash as identical
+    /// to its complement.
+    ///
+    /// Returns `min(d, bits - d)` where `d` is [`self.dist(other)`](#method.dist) and `bits` is
+    /// the number of bits in each hash, as returned by
+    /// [`Hasher::hash_bits()`](struct.Hasher.html#method.hash_bits). An image and its
+    /// negative have nearly complementary hashes for most algorithms, so this makes comparisons
+    /// invariant to inverting the luminance of the image.
+    pub fn inversion_invariant_dist(&self, other: &Self, bits: u32) -> u32 {
+        let dist = self.dist(other);
+        dist.min(bits.saturating_sub(dist))
+    }
+
     /// Create an `ImageHash` instance from the given Base64-encoded string.
     ///
     /// ## Errors:
@@ -472,6 +1241,87 @@ impl<B: HashBytes> ImageHash<B> {
     }
 }
 
+/// A hash made of a vector of floating-point features instead of bits.
+///
+/// Compared by Euclidean (L2) distance instead of Hamming distance.
+///
+/// Get an instance with [`FloatHash::color_moments()`](#method.color_moments).
+#[derive(PartialEq, Debug, Clone)]
+pub struct FloatHash {
+    features: Box<[f32]>,
+}
+
+impl FloatHash {
+    /// Create a `FloatHash` from the given feature values.
+    pub fn from_floats(features: &[f32]) -> FloatHash {
+        FloatHash { features: features.into() }
+    }
+
+    /// Get the feature values of this hash.
+    pub fn as_floats(&self) -> &[f32] { &self.features }
+
+    /// Calculate the Euclidean (L2) distance between this and `other`.
+    ///
+    /// ### Note
+    /// This return value is meaningless if these two hashes are from different algorithms.
+    ///
+    /// Returns infinity if the hashes have different numbers of features, which means they
+    /// can't be from the same algorithm.
+    pub fn dist(&self, other: &Self) -> f32 {
+        if self.features.len() != other.features.len() {
+            return f32::INFINITY;
+        }
+
+        self.features.iter().zip(other.features.iter())
+            .map(|(l, r)| (l - r) * (l - r))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    /// Get the features of this hash as bytes, each value in little-endian order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.features.iter().flat_map(|x| x.to_le_bytes().to_vec()).collect()
+    }
+
+    /// Create a `FloatHash` instance from bytes as produced by [`to_bytes()`](#method.to_bytes).
+    ///
+    /// ## Errors:
+    /// Returns a `InvalidBytesError::BytesWrongLength` error if the length of the slice is not
+    /// a multiple of 4.
+    pub fn from_bytes(bytes: &[u8]) -> Result<FloatHash, InvalidBytesError> {
+        let chunks = bytes.chunks_exact(4);
+
+        if !chunks.remainder().is_empty() {
+            return Err(InvalidBytesError::BytesWrongLength {
+                expected: (bytes.len() / 4 + 1) * 4,
+                found: bytes.len(),
+            });
+        }
+
+        let features = chunks
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<_>>();
+
+        Ok(FloatHash { features: features.into_boxed_slice() })
+    }
+
+    /// Create a `FloatHash` instance from the given Base64-encoded string.
+    ///
+    /// ## Errors:
+    /// Returns `InvalidBytesError::Base64` if the string wasn't valid base64.
+    /// Otherwise returns the same errors as `from_bytes`.
+    pub fn from_base64(encoded_hash: &str) -> Result<FloatHash, InvalidBytesError> {
+        let bytes = base64::decode(encoded_hash).map_err(InvalidBytesError::Base64)?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Get a Base64 string representing the bytes of this hash.
+    pub fn to_base64(&self) -> String {
+        base64::encode(self.to_bytes())
+    }
+}
+
 /// Provide Serde a typedef for `image::FilterType`: https://serde.rs/remote-derive.html
 /// This is automatically checked, if Serde complains then double-check with the original definition
 #[derive(Serialize, Deserialize)]
@@ -736,3 +1586,72 @@ mod test {
     }
 }
 */
+
+#[test]
+fn test_inversion_invariant() {
+    use image::Luma;
+
+    let img = GrayImage::from_fn(64, 64, |x, y| {
+        Luma([(128. + 100. * (x as f32 / 9.).sin() * (y as f32 / 7.).cos()) as u8])
+    });
+    let inverted = GrayImage::from_fn(64, 64, |x, y| Luma([255 - img.get_pixel(x, y)[0]]));
+
+    let hasher = HasherConfig::new().hash_alg(HashAlg::Mean).hash_size(5, 5).to_hasher();
+    let (hash, inv_hash) = (hasher.hash_image(&img), hasher.hash_image(&inverted));
+    assert!(hash.dist(&inv_hash) >= 23);
+    assert!(hash.inversion_invariant_dist(&inv_hash, hasher.hash_bits()) <= 2);
+
+    let hasher = HasherConfig::new().hash_alg(HashAlg::Mean).hash_size(5, 5)
+        .inversion_invariant().to_hasher();
+    let hash = hasher.hash_image(&img);
+    assert!(hash.dist(&hasher.hash_image(&inverted)) <= 2);
+    // the padding bits stay zeroed
+    assert_eq!(hash.as_bytes()[3] & 0xFE, 0);
+}
+
+#[test]
+fn test_float_hash_dist_lengths() {
+    let hash = FloatHash::from_floats(&[0.5, 0.25, 1.]);
+    assert_eq!(hash.dist(&FloatHash::from_floats(&[0.5, 0.25, 1.])), 0.);
+
+    let restored = FloatHash::from_bytes(&hash.to_bytes()[.. 8]).unwrap();
+    assert_eq!(hash.dist(&restored), f32::INFINITY);
+}
+
+#[test]
+#[should_panic(expected = "hash size must be nonzero")]
+fn test_zero_hash_size() {
+    HasherConfig::new().hash_size(0, 8).to_hasher();
+}
+
+#[test]
+fn test_dct_wavelet_simhash() {
+    use image::{imageops, GrayImage, Luma};
+
+    let img = GrayImage::from_fn(128, 128, |x, y| {
+        let (x, y) = (x as f32, y as f32);
+        Luma([(128. + 60. * (x / 9.).sin() + 40. * (y / 13. + x / 21.).cos()) as u8])
+    });
+    // blurred, brighter and with less contrast
+    let mut altered = imageops::blur(&img, 1.);
+    altered.iter_mut().for_each(|x| *x = (50 + *x as u32 * 3 / 4) as u8);
+    let other = GrayImage::from_fn(128, 128, |x, y| {
+        let (x, y) = (x as f32, y as f32);
+        Luma([(128. + 60. * (y / 7.).cos() + 40. * (x / 11. - y / 17.).sin()) as u8])
+    });
+
+    for &alg in &[HashAlg::Dct, HashAlg::Wavelet, HashAlg::SimHash] {
+        let hasher = HasherConfig::new().hash_alg(alg).to_hasher();
+        let hash = hasher.hash_image(&img);
+
+        assert_eq!(hasher.hash_bits(), 64, "{:?}", alg);
+        assert!(hash.dist(&hasher.hash_image(&altered)) <= 12, "{:?}", alg);
+        assert!(hash.dist(&hasher.hash_image(&other)) >= 20, "{:?}", alg);
+    }
+
+    // the hyperplanes only depend on the seed
+    let simhash = |seed| HasherConfig::new().hash_alg(HashAlg::SimHash).simhash_params(64, seed)
+        .to_hasher().hash_image(&img);
+    assert_eq!(simhash(7), simhash(7));
+    assert_ne!(simhash(7), simhash(8));
+}
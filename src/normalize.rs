@@ -0,0 +1,188 @@
+use image::GrayImage;
+
+/// Contrast normalization for
+/// [`HasherConfig::preproc_normalize()`](struct.HasherConfig.html#method.preproc_normalize).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Normalization {
+    /// Global histogram equalization, spreading the luminance values evenly over `0 ..= 255`.
+    ///
+    /// Further Reading:
+    /// https://en.wikipedia.org/wiki/Histogram_equalization
+    Equalize,
+    /// Contrast Limited Adaptive Histogram Equalization, which equalizes each of a grid of tiles
+    /// separately and interpolates between them, limiting how much noise in flat areas is
+    /// amplified.
+    ///
+    /// Further Reading:
+    /// https://en.wikipedia.org/wiki/Adaptive_histogram_equalization#Contrast_Limited_AHE
+    Clahe {
+        /// The number of tiles along each side of the image.
+        tiles: u32,
+        /// The height of each bin of a tile's histogram is limited to this multiple of the mean
+        /// height, with the excess spread over all the bins. Lower values limit contrast more.
+        clip_limit: f32,
+    },
+    /// Linearly stretch the luminance values so the darkest pixel is 0 and the brightest 255.
+    Stretch,
+}
+
+impl Normalization {
+    /// CLAHE with commonly used parameters: 8 x 8 tiles and a clip limit of 2.
+    pub fn clahe() -> Self {
+        Normalization::Clahe { tiles: 8, clip_limit: 2. }
+    }
+
+    /// Produce the normalized image.
+    pub(crate) fn normalize(&self, img: &GrayImage) -> GrayImage {
+        let mut img = img.clone();
+
+        // a flat image has no contrast to normalize
+        if img.iter().all(|&x| Some(&x) == img.first()) {
+            return img;
+        }
+
+        match *self {
+            Normalization::Equalize => {
+                let lut = equalize_lut(&histogram(img.iter()));
+                img.iter_mut().for_each(|x| *x = lut[*x as usize]);
+            },
+            Normalization::Clahe { tiles, clip_limit } => clahe(&mut img, tiles, clip_limit),
+            Normalization::Stretch => stretch(&mut img),
+        }
+
+        img
+    }
+}
+
+fn histogram<'a, I: Iterator<Item = &'a u8>>(vals: I) -> [u32; 256] {
+    let mut hist = [0u32; 256];
+    vals.for_each(|&x| hist[x as usize] += 1);
+    hist
+}
+
+/// Map each luminance value to its position in the cumulative histogram, scaled to
+/// `0 ..= 255` so the smallest value present maps to 0.
+fn equalize_lut(hist: &[u32; 256]) -> [u8; 256] {
+    let mut lut = [0u8; 256];
+    let total: u32 = hist.iter().sum();
+    let first = hist.iter().cloned().find(|&count| count > 0).unwrap_or(0);
+
+    // a single value (or nothing at all) has nothing to spread out
+    if total == first {
+        for (i, out) in lut.iter_mut().enumerate() {
+            *out = i as u8;
+        }
+        return lut;
+    }
+
+    let mut cumulative = 0;
+
+    for (&count, out) in hist.iter().zip(lut.iter_mut()) {
+        cumulative += count;
+        *out = (cumulative.saturating_sub(first) as f32 * 255. / (total - first) as f32).round() as u8;
+    }
+
+    lut
+}
+
+fn stretch(img: &mut GrayImage) {
+    let (min, max) = img.iter().fold((255u8, 0u8), |(min, max), &x| (min.min(x), max.max(x)));
+    let scale = 255. / (max - min) as f32;
+    img.iter_mut().for_each(|x| *x = ((*x - min) as f32 * scale).round() as u8);
+}
+
+fn clahe(img: &mut GrayImage, tiles: u32, clip_limit: f32) {
+    let (width, height) = img.dimensions();
+
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // every tile needs at least one pixel
+    let (tiles_x, tiles_y) = (tiles.clamp(1, width), tiles.clamp(1, height));
+    let (tile_width, tile_height) = (width as f32 / tiles_x as f32, height as f32 / tiles_y as f32);
+
+    let luts: Vec<[u8; 256]> = (0 .. tiles_y).flat_map(|ty| (0 .. tiles_x).map(move |tx| (tx, ty)))
+        .map(|(tx, ty)| {
+            let (x0, x1) = (tx * width / tiles_x, (tx + 1) * width / tiles_x);
+            let (y0, y1) = (ty * height / tiles_y, (ty + 1) * height / tiles_y);
+
+            let mut hist = histogram((y0 .. y1).flat_map(|y| {
+                let row = (y * width) as usize;
+                img.as_raw()[row + x0 as usize .. row + x1 as usize].iter()
+            }));
+
+            clip_histogram(&mut hist, clip_limit);
+            equalize_lut(&hist)
+        })
+        .collect();
+
+    // the position of a pixel in the grid of tile centers, and the weight of the next tile
+    let grid_pos = |pos: u32, tile_size: f32, tiles: u32| {
+        let pos = ((pos as f32 + 0.5) / tile_size - 0.5).clamp(0., (tiles - 1) as f32);
+        let first = pos as u32;
+        (first, (first + 1).min(tiles - 1), pos - first as f32)
+    };
+
+    for (x, y, px) in img.enumerate_pixels_mut() {
+        let (tx0, tx1, wx) = grid_pos(x, tile_width, tiles_x);
+        let (ty0, ty1, wy) = grid_pos(y, tile_height, tiles_y);
+
+        let val = px[0] as usize;
+        let lookup = |tx: u32, ty: u32| luts[(ty * tiles_x + tx) as usize][val] as f32;
+
+        let top = lookup(tx0, ty0) * (1. - wx) + lookup(tx1, ty0) * wx;
+        let bottom = lookup(tx0, ty1) * (1. - wx) + lookup(tx1, ty1) * wx;
+
+        px[0] = (top * (1. - wy) + bottom * wy).round() as u8;
+    }
+}
+
+/// Limit each bin to `clip_limit` times the mean bin height and spread the excess evenly.
+fn clip_histogram(hist: &mut [u32; 256], clip_limit: f32) {
+    let total: u32 = hist.iter().sum();
+    let limit = ((clip_limit * total as f32 / 256.).ceil() as u32).max(1);
+
+    let excess: u32 = hist.iter_mut().map(|count| {
+        let over = count.saturating_sub(limit);
+        *count -= over;
+        over
+    }).sum();
+
+    let (share, remainder) = (excess / 256, excess % 256);
+
+    for (i, count) in hist.iter_mut().enumerate() {
+        *count += share + (i < remainder as usize) as u32;
+    }
+}
+
+#[test]
+fn test_normalization() {
+    use image::Luma;
+    use {HashAlg, HasherConfig};
+
+    let img = GrayImage::from_fn(64, 64, |x, y| Luma([(x * 2 + y) as u8]));
+    // darker and lower contrast, with the ordering of the values preserved
+    let dimmed = GrayImage::from_fn(64, 64, |x, y| Luma([40 + (x * 2 + y) as u8 / 3]));
+
+    let stretched = Normalization::Stretch.normalize(&dimmed);
+    assert_eq!(stretched.iter().min(), Some(&0));
+    assert_eq!(stretched.iter().max(), Some(&255));
+
+    let equalized = Normalization::Equalize.normalize(&dimmed);
+    assert_eq!(equalized.iter().min(), Some(&0));
+    assert_eq!(equalized.iter().max(), Some(&255));
+
+    // a flat image is left alone
+    let flat = GrayImage::from_pixel(16, 16, Luma([90]));
+    assert_eq!(Normalization::clahe().normalize(&flat), flat);
+    assert_eq!(Normalization::Equalize.normalize(&flat), flat);
+
+    for &normalization in &[Normalization::Equalize, Normalization::clahe(), Normalization::Stretch] {
+        let hasher = HasherConfig::new().hash_alg(HashAlg::Mean).hash_size(16, 16)
+            .preproc_normalize(normalization).to_hasher();
+
+        assert!(hasher.hash_image(&img).dist(&hasher.hash_image(&dimmed)) <= 8,
+                "{:?}", normalization);
+    }
+}
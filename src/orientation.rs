@@ -0,0 +1,127 @@
+use std::io::{BufRead, Seek};
+
+use image::DynamicImage;
+
+use exif::{In, Reader, Tag};
+
+/// Read the EXIF Orientation tag from an image file, from 1 (upright) to 8.
+///
+/// Returns 1 if the file has no EXIF data or the tag is missing or invalid.
+pub fn read_orientation<R: BufRead + Seek>(reader: &mut R) -> u32 {
+    Reader::new().read_from_container(reader).ok()
+        .and_then(|exif| exif.get_field(Tag::Orientation, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0)))
+        .filter(|orientation| (1 ..= 8).contains(orientation))
+        .unwrap_or(1)
+}
+
+/// Flip and rotate the decoded image as described by the EXIF orientation so it's upright.
+pub fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        // transpose
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        // transverse
+        7 => img.rotate90().flipv(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// The APP1 segment of a JPEG file with just the EXIF Orientation tag, big-endian.
+#[cfg(test)]
+fn exif_segment(orientation: u8) -> Vec<u8> {
+    let mut segment = vec![0xFF, 0xE1, 0x00, 0x22];
+    segment.extend_from_slice(b"Exif\0\0MM\0\x2A\0\0\0\x08");
+    segment.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01,
+                                0x00, orientation, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    segment
+}
+
+#[test]
+fn test_orientation() {
+    use std::io::Cursor;
+    use image::{GenericImageView, GrayImage, Luma};
+
+    // a JPEG header with just an EXIF segment
+    let mut jpeg = vec![0xFF, 0xD8];
+    jpeg.extend(exif_segment(6));
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+
+    assert_eq!(read_orientation(&mut Cursor::new(&jpeg)), 6);
+    assert_eq!(read_orientation(&mut Cursor::new(b"not an image")), 1);
+
+    // stored as:
+    // 0 1 2
+    // 3 4 5
+    let stored = GrayImage::from_fn(3, 2, |x, y| Luma([(y * 3 + x) as u8]));
+    let stored = DynamicImage::ImageLuma8(stored);
+    let upright = |orientation| {
+        let img = apply_orientation(stored.clone(), orientation);
+        assert_eq!(img.dimensions(), (2, 3), "{}", orientation);
+        img.to_luma8().into_raw()
+    };
+
+    // rotated 90 degrees clockwise
+    assert_eq!(upright(6), [3, 0, 4, 1, 5, 2]);
+    // transposed
+    assert_eq!(upright(5), [0, 3, 1, 4, 2, 5]);
+    // transversed
+    assert_eq!(upright(7), [5, 2, 4, 1, 3, 0]);
+    // rotated 90 degrees counterclockwise
+    assert_eq!(upright(8), [2, 5, 1, 4, 0, 3]);
+
+    // every orientation is undone by its inverse transform
+    let inverses = [1, 2, 3, 4, 5, 8, 7, 6];
+
+    for (orientation, &inverse) in (1 ..= 8).zip(&inverses) {
+        let roundtrip = apply_orientation(apply_orientation(stored.clone(), inverse), orientation);
+        assert_eq!(roundtrip.to_bytes(), stored.to_bytes(), "{}", orientation);
+    }
+}
+
+#[test]
+fn test_hash_reader() {
+    use std::io::Cursor;
+    use image::{imageops, ColorType, RgbImage, Rgb};
+    use image::jpeg::JpegEncoder;
+    use HasherConfig;
+
+    let upright = RgbImage::from_fn(64, 48, |x, y| {
+        let val = ((x as i32 - 20).abs() * 4 + y as i32 * 2) as u8;
+        Rgb([val, val, 100])
+    });
+    // a phone would store it sideways, to be rotated 90 degrees clockwise for display
+    let stored = imageops::rotate270(&upright);
+
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, 100)
+        .encode(&stored, stored.width(), stored.height(), ColorType::Rgb8)
+        .unwrap();
+
+    // insert the EXIF segment right after the start of image marker
+    let mut jpeg = encoded[.. 2].to_vec();
+    jpeg.extend(exif_segment(6));
+    jpeg.extend_from_slice(&encoded[2 ..]);
+
+    let decoded = image::load_from_memory(&jpeg).unwrap();
+
+    let hasher = HasherConfig::new().to_hasher();
+    let hash = hasher.hash_reader(Cursor::new(&jpeg)).unwrap();
+
+    assert_eq!(hash, hasher.hash_image(&decoded.rotate90()));
+    assert!(hash.dist(&hasher.hash_image(&upright)) <= 2);
+
+    let path = ::std::env::temp_dir().join("img_hash_test_hash_reader.jpg");
+    ::std::fs::write(&path, &jpeg).unwrap();
+    assert_eq!(hasher.hash_path(&path).unwrap(), hash);
+    ::std::fs::remove_file(&path).unwrap();
+
+    let ignoring = HasherConfig::new().ignore_exif_orientation().to_hasher();
+    let sideways = ignoring.hash_reader(Cursor::new(&jpeg)).unwrap();
+    assert_eq!(sideways, ignoring.hash_image(&decoded));
+    assert_ne!(sideways, hash);
+}
@@ -20,7 +20,7 @@ pub trait HashBytes {
 
     /// Return the maximum capacity of this type, in bits.
     ///
-    /// If this type has an arbitrary/theoretically infinite capacity, return `usize::max_value()`.
+    /// If this type has an arbitrary/theoretically infinite capacity, return `usize::MAX`.
     fn max_bits() -> usize;
 
     /// Get the hash bytes as a slice.
@@ -35,7 +35,7 @@ impl HashBytes for Box<[u8]> {
     }
 
     fn max_bits() -> usize {
-        usize::max_value()
+        usize::MAX
     }
 
     fn as_slice(&self) -> &[u8] { self }
@@ -47,7 +47,7 @@ impl HashBytes for Vec<u8> {
     }
 
     fn max_bits() -> usize {
-        usize::max_value()
+        usize::MAX
     }
 
     fn as_slice(&self) -> &[u8] { self }
@@ -122,7 +122,7 @@ pub trait Image: GenericImageView + 'static {
     type Buf: Image + DiffImage;
 
     /// Grayscale the image, reducing to 8 bit depth and dropping the alpha channel.
-    fn to_grayscale(&self) -> Cow<GrayImage>;
+    fn to_grayscale(&self) -> Cow<'_, GrayImage>;
 
     /// Blur the image with the given `Gaussian` sigma.
     fn blur(&self, sigma: f32) -> Self::Buf;
@@ -151,7 +151,7 @@ impl<P: 'static, C: 'static> Image for ImageBuffer<P, C>
     where P: Pixel<Subpixel = u8>, C: ops::Deref<Target=[u8]> {
     type Buf = ImageBuffer<P, Vec<u8>>;
 
-    fn to_grayscale(&self) -> Cow<GrayImage> {
+    fn to_grayscale(&self) -> Cow<'_, GrayImage> {
         Cow::Owned(imageops::grayscale(self))
     }
 
@@ -167,7 +167,7 @@ impl<P: 'static, C: 'static> Image for ImageBuffer<P, C>
     where P: Pixel<Subpixel = u8>, C: ops::Deref<Target=[u8]> {
     type Buf = ImageBuffer<P, Vec<u8>>;
 
-    default fn to_grayscale(&self) -> Cow<GrayImage> {
+    default fn to_grayscale(&self) -> Cow<'_, GrayImage> {
         Cow::Owned(imageops::grayscale(self))
     }
 
@@ -187,8 +187,8 @@ impl<P: 'static> DiffImage for ImageBuffer<P, Vec<u8>> where P: Pixel<Subpixel =
 impl Image for DynamicImage {
     type Buf = image::RgbaImage;
 
-    fn to_grayscale(&self) -> Cow<GrayImage> {
-        self.as_luma8().map_or_else(|| Cow::Owned(self.to_luma()), Cow::Borrowed)
+    fn to_grayscale(&self) -> Cow<'_, GrayImage> {
+        self.as_luma8().map_or_else(|| Cow::Owned(self.to_luma8()), Cow::Borrowed)
     }
 
     fn blur(&self, sigma: f32) -> Self::Buf { imageops::blur(self, sigma) }
@@ -203,7 +203,7 @@ impl Image for GrayImage {
     // type Buf = GrayImage;
 
     // Avoids copying
-    fn to_grayscale(&self) -> Cow<GrayImage> {
+    fn to_grayscale(&self) -> Cow<'_, GrayImage> {
         Cow::Borrowed(self)
     }
 }